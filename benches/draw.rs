@@ -0,0 +1,70 @@
+//! Measures the time to draw and tessellate frames of a large puzzle with a
+//! twist animation running continuously.
+//!
+//! Run with `cargo bench --bench draw`.
+
+use std::time::{Duration, Instant};
+
+use egui::{CentralPanel, Context, Frame, Pos2, RawInput, Rect, vec2};
+use sphenic_biaxe::{Algorithm, Preferences, PuzzleConfig, PuzzleState, PuzzleView};
+
+const WARMUP_FRAMES: u32 = 50;
+const FRAMES: u32 = 500;
+
+fn main() {
+    for puzzle in ["M5F2", "M16M16", "M16M16x2"] {
+        let config: PuzzleConfig = puzzle.parse().expect("invalid puzzle");
+        let (draw, tessellate) = bench(config);
+        println!(
+            "{puzzle:>10}: {:>8.1} µs draw + {:>8.1} µs tessellate per frame",
+            micros_per_frame(draw),
+            micros_per_frame(tessellate),
+        );
+    }
+}
+
+/// Returns the total time spent drawing and tessellating [`FRAMES`] frames.
+fn bench(config: PuzzleConfig) -> (Duration, Duration) {
+    let ctx = Context::default();
+    let mut view = PuzzleView::new(config, PuzzleState::new(config));
+    let prefs = Preferences {
+        // Keep each twist on screen for many frames.
+        twist_duration: 1000.0,
+        ..Default::default()
+    };
+    let alg: Algorithm = "L R' L' R".parse().expect("invalid algorithm");
+
+    let (mut draw, mut tessellate) = (Duration::ZERO, Duration::ZERO);
+    for frame in 0..WARMUP_FRAMES + FRAMES {
+        if frame % 100 == 0 {
+            for twist in alg.0.iter().copied() {
+                view.apply_twist(twist);
+            }
+        }
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, vec2(1280.0, 720.0))),
+            ..Default::default()
+        };
+
+        let start = Instant::now();
+        let output = ctx.run(input, |ctx| {
+            CentralPanel::default()
+                .frame(Frame::NONE)
+                .show(ctx, |ui| view.show_puzzle(ui, &prefs));
+        });
+        let drawn = Instant::now();
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let tessellated = Instant::now();
+        std::hint::black_box(primitives);
+
+        if frame >= WARMUP_FRAMES {
+            draw += drawn - start;
+            tessellate += tessellated - drawn;
+        }
+    }
+    (draw, tessellate)
+}
+
+fn micros_per_frame(total: Duration) -> f64 {
+    total.as_secs_f64() * 1e6 / FRAMES as f64
+}
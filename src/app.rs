@@ -1,6 +1,14 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-use crate::{Preferences, PuzzleView};
+use crate::bld::BldAttempt;
+use crate::daily::{self, DailyChallenge, DailyResult};
+use crate::fmc::FmcAttempt;
+use crate::timer::{INSPECTION_TIME, INSPECTION_WARNINGS, Penalty, SolveTimer, TimerStart};
+use crate::trainer::{self, Goal, GoalTimes, TrainerAttempt, TrainerPhase};
+use crate::util::{format_duration, time_seeded_rng};
+use crate::{Algorithm, Grip, Metric, Preferences, PuzzleView, Round, RoundSolve, TableStatus};
 
 #[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
 enum Tab {
@@ -17,6 +25,28 @@ pub struct App {
     pub prefs: Preferences,
     #[serde(skip)]
     tab: Tab,
+    /// Blindfolded attempt in progress or just finished.
+    #[serde(skip)]
+    bld: Option<BldAttempt>,
+    #[serde(skip)]
+    timer: SolveTimer,
+    /// Competition round in progress.
+    #[serde(skip)]
+    round: Option<Round>,
+    /// Daily challenge in progress.
+    #[serde(skip)]
+    daily: Option<DailyChallenge>,
+    /// Result of the first attempt at each daily challenge, by day.
+    daily_results: BTreeMap<u32, DailyResult>,
+    /// Fewest-moves challenge in progress.
+    #[serde(skip)]
+    fmc: Option<FmcAttempt>,
+    /// Trainer goal in progress.
+    #[serde(skip)]
+    trainer: Option<TrainerAttempt>,
+    /// Statistics of each trainer goal that has been done, by puzzle and
+    /// goal.
+    trainer_times: BTreeMap<String, GoalTimes>,
 }
 
 impl App {
@@ -35,6 +65,13 @@ impl App {
     }
 
     fn show_configuration(&mut self, ui: &mut egui::Ui) {
+        if self.prefs.touch_mode {
+            let spacing = ui.spacing_mut();
+            spacing.interact_size.y = 32.0;
+            spacing.button_padding = egui::vec2(10.0, 6.0);
+            spacing.item_spacing.y = 8.0;
+        }
+
         ui.heading("Configuration");
 
         ui.add_space(ui.spacing().item_spacing.y);
@@ -45,8 +82,11 @@ impl App {
             ui.horizontal(|ui| {
                 if ui.button("Scramble").clicked() {
                     self.puzzle.scramble();
+                    self.end_challenges();
+                    self.timer = SolveTimer::scrambled(self.prefs.inspection);
                 }
-                if self.puzzle.is_solved() {
+                // Don't reveal whether a blindfolded solve is finished.
+                if self.puzzle.is_solved() && !self.puzzle.is_blindfolded() {
                     ui.disable();
                 }
                 if ui.button("Reset").clicked() {
@@ -55,12 +95,94 @@ impl App {
             })
         });
 
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Timer");
+            self.prefs.show_timer_prefs(ui);
+            if self.round.is_some() {
+                // Penalties are edited in the round.
+            } else if let SolveTimer::Stopped(result) = &mut self.timer {
+                ui.horizontal(|ui| {
+                    ui.label(format!("Last solve: {result}"));
+                    ui.selectable_value(&mut result.penalty, Penalty::None, "OK");
+                    ui.selectable_value(&mut result.penalty, Penalty::PlusTwo, "+2");
+                    ui.selectable_value(&mut result.penalty, Penalty::Dnf, "DNF");
+                });
+            }
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Trainer");
+            self.show_trainer(ui);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Daily challenge");
+            self.show_daily(ui);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Competition round");
+            self.show_round(ui);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Fewest moves");
+            self.show_fmc(ui);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Blindfolded");
+            self.show_bld(ui);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("View");
+            self.puzzle.show_view_controls(ui);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Piece tracking");
+            self.puzzle.show_tracking(ui, &self.prefs);
+        });
+
         ui.group(|ui| {
             ui.set_width(ui.available_width());
             ui.strong("Visuals");
             self.prefs.show_visuals_prefs(ui);
         });
 
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Labels");
+            self.prefs.show_label_prefs(ui, self.puzzle.config());
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Colors");
+            self.prefs.show_color_prefs(ui, self.puzzle.config());
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Export");
+            self.puzzle.show_export(ui, &self.prefs);
+        });
+
+        ui.group(|ui| {
+            ui.set_width(ui.available_width());
+            ui.strong("Animation");
+            self.prefs.show_animation_prefs(ui);
+        });
+
         ui.group(|ui| {
             ui.set_width(ui.available_width());
             ui.strong("Interaction");
@@ -81,6 +203,14 @@ impl App {
             ui.add_space(ui.spacing().item_spacing.y);
             ui.label("• Scroll up — rotate counterclockwise");
             ui.label("• Scroll down — rotate clockwise");
+            ui.add_space(ui.spacing().item_spacing.y);
+            ui.label("• Ctrl+click — track a piece");
+            ui.label("• Ctrl+scroll — zoom");
+            ui.label("• Middle drag — pan");
+            ui.add_space(ui.spacing().item_spacing.y);
+            ui.label("• Swipe along the edge of a disk — rotate by one sector");
+            ui.label("• Rotate two fingers over a disk — rotate it");
+            ui.label("• Pinch or move two fingers — zoom and pan");
 
             // Keyboard controls
             ui.horizontal(|ui| {
@@ -94,29 +224,626 @@ impl App {
                     );
                 }
             });
+            ui.label("• Escape — finish all twist animations");
+            ui.label("• Shift+Escape — finish all but the current twist animation");
+            ui.label("• Ctrl+Z — undo the last twist");
         });
 
+        // Name the pieces with the current label scheme, so that the
+        // description matches what is drawn.
+        let cfg = self.puzzle.config();
+        let labels = &self.prefs.labels;
+        let names = |grip, slots: Vec<u32>| {
+            let mut names: Vec<String> = slots
+                .iter()
+                .take(3)
+                .map(|&i| labels.sector_name(cfg, grip, i))
+                .collect();
+            if slots.len() > 3 {
+                names.push("…".to_string());
+            }
+            names.join(", ")
+        };
+        let center = (0..cfg.shared)
+            .map(|i| labels.sector_name(cfg, Grip::A, i))
+            .collect::<Vec<_>>()
+            .join(" and ");
+        let left = names(Grip::A, (cfg.shared..cfg.a).collect());
+        let right = names(Grip::B, (cfg.shared..cfg.b).rev().collect());
         ui.group(|ui| {
             ui.set_width(ui.available_width());
             ui.strong("Solved state");
-            ui.label("The center has the dot piece");
+            ui.label(match cfg.shared {
+                1 => format!("The center has the {center} piece"),
+                _ => format!("The center has the {center} pieces"),
+            });
             ui.add_space(ui.spacing().item_spacing.y);
-            ui.label("The left circle has letters increasing clockwise from the dot");
+            ui.label(format!(
+                "The left circle has {left} clockwise from the center"
+            ));
             ui.add_space(ui.spacing().item_spacing.y);
-            ui.label("The right circle has numbers increasing counterclockwise from the dot");
+            ui.label(format!(
+                "The right circle has {right} counterclockwise from the center"
+            ));
         });
     }
 
+    fn show_bld(&mut self, ui: &mut egui::Ui) {
+        let solved = self.puzzle.is_solved();
+        let mut cancel = false;
+        match &mut self.bld {
+            None | Some(BldAttempt::Done(_)) => {
+                if let Some(BldAttempt::Done(result)) = &self.bld {
+                    ui.label(format!(
+                        "{}: {} (memo {}, execution {})",
+                        if result.success { "Success" } else { "DNF" },
+                        format_duration(result.total()),
+                        format_duration(result.memo),
+                        format_duration(result.execution),
+                    ));
+                }
+                if ui.button("Start attempt").clicked() {
+                    self.puzzle.scramble();
+                    self.end_challenges();
+                    self.bld = Some(BldAttempt::start());
+                    self.timer = SolveTimer::Idle;
+                }
+            }
+            Some(attempt) => {
+                ui.label(match attempt.is_blindfolded() {
+                    false => "Memorize the pieces, then put on the blindfold.",
+                    true => "Solve the puzzle without seeing the pieces.",
+                });
+                ui.horizontal(|ui| {
+                    if !attempt.is_blindfolded() {
+                        if ui.button("Put on blindfold (Space)").clicked() {
+                            attempt.put_on_blindfold();
+                        }
+                    } else if ui.button("Done (Enter)").clicked() {
+                        attempt.finish(solved);
+                    }
+                    cancel = ui.button("Cancel").clicked();
+                });
+            }
+        }
+        if cancel {
+            self.bld = None;
+        }
+    }
+
+    /// Handles keyboard shortcuts for the blindfolded attempt and keeps the
+    /// puzzle view in sync with it.
+    fn update_bld(&mut self, ctx: &egui::Context) {
+        // Resetting the puzzle or changing its configuration ends the attempt.
+        if !self.puzzle.was_scrambled() {
+            self.bld = None;
+        }
+
+        if let Some(attempt) = &mut self.bld {
+            if !ctx.wants_keyboard_input() {
+                if ctx.input(|input| input.key_pressed(egui::Key::Space)) {
+                    attempt.put_on_blindfold();
+                }
+                if attempt.is_blindfolded()
+                    && ctx.input(|input| input.key_pressed(egui::Key::Enter))
+                {
+                    attempt.finish(self.puzzle.is_solved());
+                }
+            }
+            if attempt.is_running() {
+                ctx.request_repaint();
+            }
+        }
+
+        self.puzzle
+            .set_blindfolded(self.bld.is_some_and(|attempt| attempt.is_blindfolded()));
+    }
+
+    /// Ends any blindfolded attempt, competition round, daily challenge,
+    /// fewest-moves challenge, or trainer goal.
+    fn end_challenges(&mut self) {
+        self.bld = None;
+        self.round = None;
+        self.daily = None;
+        self.fmc = None;
+        self.trainer = None;
+    }
+
+    fn show_trainer(&mut self, ui: &mut egui::Ui) {
+        let error_id = egui::Id::new("trainer_error");
+        let mut start = None;
+        let mut next = false;
+        let mut stop = false;
+
+        if let Some(attempt) = &self.trainer {
+            ui.label(format!(
+                "Goal {} of {}: {}",
+                attempt.goal + 1,
+                attempt.goals.len(),
+                attempt.current_goal().name,
+            ));
+            ui.horizontal(|ui| {
+                if ui.button("New position").clicked() {
+                    start = Some(attempt.goal);
+                }
+                if matches!(attempt.phase, TrainerPhase::Done(_)) && attempt.has_next_goal() {
+                    next = ui
+                        .button("Next goal")
+                        .on_hover_text("Continue from this position")
+                        .clicked();
+                }
+                stop = ui.button("Stop").clicked();
+            });
+        } else {
+            ui.label("Practice partial goals, starting with the earlier ones done.");
+        }
+
+        let config = self.puzzle.config();
+        egui::Grid::new("trainer_goals").show(ui, |ui| {
+            ui.label("");
+            ui.label("Done");
+            ui.label("Best");
+            ui.label("Mean");
+            ui.end_row();
+            for (i, goal) in Goal::all(config, &self.prefs.labels).iter().enumerate() {
+                if ui
+                    .button(format!("{}. {}", i + 1, goal.name))
+                    .on_hover_text("Practice this goal")
+                    .clicked()
+                {
+                    start = Some(i);
+                }
+                let times = self
+                    .trainer_times
+                    .get(&goal.stats_key(config))
+                    .copied()
+                    .unwrap_or_default();
+                ui.label(times.count.to_string());
+                if let Some(mean) = times.mean() {
+                    ui.label(format_duration(times.best));
+                    ui.label(format_duration(mean));
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(goal) = ui.data(|data| data.get_temp::<usize>(error_id)) {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!(
+                    "Goal {} has too many pieces to set up on this puzzle. \
+                     Reach it from goal {} with \"Next goal\".",
+                    goal + 1,
+                    goal,
+                ),
+            );
+        }
+
+        if let Some(goal) = start {
+            let mut rng = time_seeded_rng();
+            ui.data_mut(|data| data.remove::<usize>(error_id));
+            match trainer::starting_position(config, goal, &mut rng) {
+                Some(state) => {
+                    self.puzzle.scramble_to(config, state);
+                    self.end_challenges();
+                    self.timer = SolveTimer::Idle;
+                    self.trainer = Some(TrainerAttempt::new(config, goal, &self.prefs.labels));
+                }
+                None => ui.data_mut(|data| data.insert_temp(error_id, goal)),
+            }
+        }
+        if next {
+            if let (Some(attempt), Some(state)) = (&mut self.trainer, self.puzzle.state()) {
+                self.puzzle.scramble_to(config, state.clone());
+                *attempt = TrainerAttempt::new(config, attempt.goal + 1, &self.prefs.labels);
+            }
+        }
+        if stop {
+            self.trainer = None;
+        }
+    }
+
+    /// Times the trainer goal and records it once it is done.
+    fn update_trainer(&mut self, ctx: &egui::Context) {
+        let Some(attempt) = &mut self.trainer else {
+            return;
+        };
+        let Some(state) = self
+            .puzzle
+            .state()
+            .filter(|_| self.puzzle.was_scrambled() && self.puzzle.config() == attempt.config)
+        else {
+            self.trainer = None;
+            return;
+        };
+        let twisted = !self.puzzle.history().is_empty();
+        if let Some(time) = attempt.update(state, twisted) {
+            let key = attempt.current_goal().stats_key(attempt.config);
+            self.trainer_times.entry(key).or_default().add(time);
+        }
+        if matches!(attempt.phase, TrainerPhase::Running { .. }) {
+            ctx.request_repaint();
+        }
+    }
+
+    fn show_daily(&mut self, ui: &mut egui::Ui) {
+        let today = daily::today();
+        ui.label(format!("Today: {}", daily::format_day(today)));
+        let today_result = self.daily_results.get(&today);
+        if let Some(result) = today_result {
+            ui.label(format!("Your result: {}", format_daily_result(result)));
+        }
+        if let Some(challenge) = self.daily.as_ref().filter(|c| c.day == today) {
+            ui.label(match challenge.optimal_moves {
+                Some(moves) => format!("{}, optimal solution: {moves} moves", challenge.config),
+                None => format!("{}, optimal solution: computing…", challenge.config),
+            });
+        }
+
+        let start = match today_result {
+            None => ui.button("Start"),
+            Some(_) => ui
+                .button("Try again")
+                .on_hover_text("Only your first attempt is saved"),
+        };
+        if start.clicked() {
+            let challenge = DailyChallenge::for_day(today);
+            self.puzzle
+                .scramble_to(challenge.config, challenge.scramble.clone());
+            self.timer = SolveTimer::scrambled(self.prefs.inspection);
+            self.end_challenges();
+            self.daily = Some(challenge);
+        }
+
+        if !self.daily_results.is_empty() {
+            ui.collapsing("Past results", |ui| {
+                egui::Grid::new("daily_results").show(ui, |ui| {
+                    for (&day, result) in self.daily_results.iter().rev() {
+                        ui.label(daily::format_day(day));
+                        ui.label(format_daily_result(result));
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+    }
+
+    fn show_round(&mut self, ui: &mut egui::Ui) {
+        let Some(round) = &mut self.round else {
+            let seed_id = egui::Id::new("round_seed");
+            let count_id = egui::Id::new("round_count");
+            let mut seed = ui.data_mut(|data| *data.get_temp_mut_or(seed_id, 0_u64));
+            let mut count = ui.data_mut(|data| *data.get_temp_mut_or(count_id, 5_usize));
+            ui.horizontal(|ui| {
+                ui.label("Seed");
+                ui.add(egui::DragValue::new(&mut seed));
+                if ui.button("Random").clicked() {
+                    seed = random_seed();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Solves");
+                for n in [3, 5, 12] {
+                    ui.selectable_value(&mut count, n, n.to_string());
+                }
+            });
+            if ui
+                .button("Start round")
+                .on_hover_text("Everyone who uses the same puzzle and seed gets the same scrambles")
+                .clicked()
+            {
+                let round = Round::new(self.puzzle.config(), seed, count);
+                self.puzzle.scramble_to(round.config, round.scramble(0));
+                self.timer = SolveTimer::scrambled(self.prefs.inspection);
+                self.end_challenges();
+                self.round = Some(round);
+            }
+            ui.data_mut(|data| {
+                data.insert_temp(seed_id, seed);
+                data.insert_temp(count_id, count);
+            });
+            return;
+        };
+
+        ui.label(format!(
+            "{} round with seed {}: solve {} of {}",
+            round.config,
+            round.seed,
+            (round.solves.len() + 1).min(round.count),
+            round.count,
+        ));
+        egui::Grid::new("round_solves").show(ui, |ui| {
+            for (i, solve) in round.solves.iter_mut().enumerate() {
+                let result = &mut solve.result;
+                ui.label(format!("{}.", i + 1));
+                ui.label(result.to_string());
+                ui.horizontal(|ui| {
+                    ui.selectable_value(&mut result.penalty, Penalty::None, "OK");
+                    ui.selectable_value(&mut result.penalty, Penalty::PlusTwo, "+2");
+                    ui.selectable_value(&mut result.penalty, Penalty::Dnf, "DNF");
+                });
+                ui.end_row();
+            }
+        });
+
+        let mut end = false;
+        ui.horizontal(|ui| {
+            if let Some(average) = round.average() {
+                ui.strong(format!("Average: {average}"));
+                if ui.button("Copy results file").clicked() {
+                    match round.to_file() {
+                        Ok(file) => ui.ctx().copy_text(file),
+                        Err(e) => log::error!("error saving round: {e}"),
+                    }
+                }
+            } else if matches!(self.timer, SolveTimer::Stopped(_))
+                && ui.button("Next scramble").clicked()
+            {
+                self.puzzle
+                    .scramble_to(round.config, round.scramble(round.solves.len()));
+                self.timer = SolveTimer::scrambled(self.prefs.inspection);
+            }
+            end = ui.button("End round").clicked();
+        });
+        if end {
+            self.round = None;
+        }
+    }
+
+    fn show_fmc(&mut self, ui: &mut egui::Ui) {
+        self.prefs.show_fmc_prefs(ui);
+        let metric = self.prefs.fmc_metric;
+
+        let Some(fmc) = &mut self.fmc else {
+            if ui
+                .button("Start challenge")
+                .on_hover_text("Find the shortest solution you can, with no time limit")
+                .clicked()
+            {
+                self.puzzle.scramble();
+                self.end_challenges();
+                self.timer = SolveTimer::Idle;
+                if let Some(state) = self.puzzle.state() {
+                    self.fmc = Some(FmcAttempt::new(self.puzzle.config(), state.clone()));
+                }
+                // Start computing the optimal solution while the user
+                // searches for theirs.
+                let config = self.puzzle.config();
+                self.puzzle.distance_tables().get(config, metric);
+            }
+            return;
+        };
+
+        ui.label("Enter a solution, or twist the puzzle and use your twists.");
+        ui.add(
+            egui::TextEdit::multiline(&mut fmc.solution)
+                .hint_text("L R2' L'")
+                .desired_rows(2)
+                .desired_width(f32::INFINITY)
+                .font(egui::TextStyle::Monospace),
+        );
+        ui.horizontal_wrapped(|ui| {
+            if ui
+                .add_enabled(!self.puzzle.history().is_empty(), egui::Button::new("Undo"))
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                self.puzzle.undo();
+            }
+            if ui.button("Use twists").clicked() {
+                fmc.solution = Algorithm(self.puzzle.history().to_vec()).to_string();
+            }
+            if ui
+                .button("Play solution")
+                .on_hover_text("Twist the scrambled puzzle through the solution")
+                .clicked()
+            {
+                self.puzzle.scramble_to(fmc.config, fmc.scramble.clone());
+                match fmc.solution.parse::<Algorithm>() {
+                    Ok(alg) => alg.0.into_iter().for_each(|t| self.puzzle.apply_twist(t)),
+                    Err(e) => fmc.result = Some(Err(e)),
+                }
+            }
+            if ui.button("Restart").clicked() {
+                self.puzzle.scramble_to(fmc.config, fmc.scramble.clone());
+            }
+        });
+
+        let mut end = false;
+        ui.horizontal(|ui| {
+            if ui.button("Submit").clicked() {
+                fmc.submit(metric);
+            }
+            end = ui.button("End challenge").clicked();
+        });
+        match &fmc.result {
+            None => (),
+            Some(Ok(result)) => {
+                let optimal = match self.puzzle.distance_tables().get(fmc.config, result.metric) {
+                    TableStatus::Ready(table) => match table.distance(&fmc.scramble) {
+                        Some(optimal) if result.moves as u32 == optimal => "optimal!".to_string(),
+                        Some(optimal) => format!("optimal {optimal}"),
+                        None => "optimal unknown for this puzzle".to_string(),
+                    },
+                    TableStatus::Computing => "computing optimal…".to_string(),
+                    TableStatus::TooBig => "optimal unknown for this puzzle".to_string(),
+                };
+                ui.strong(format!(
+                    "{} moves ({}, {optimal})",
+                    result.moves, result.metric,
+                ));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Invalid solution: {e}"),
+                );
+            }
+        }
+        if end {
+            self.fmc = None;
+        }
+    }
+
+    /// Fills in the optimal move count of the daily challenge once its
+    /// distance table has been computed.
+    fn update_daily(&mut self) {
+        let Some(daily) = self.daily.as_mut().filter(|d| d.optimal_moves.is_none()) else {
+            return;
+        };
+        let table = self
+            .puzzle
+            .distance_tables()
+            .get(daily.config, Metric::Disk);
+        daily.optimal_moves = table
+            .ready()
+            .and_then(|table| table.distance(&daily.scramble));
+        // The first attempt may have been solved before the table was ready.
+        if let Some(result) = self.daily_results.get_mut(&daily.day) {
+            result.optimal_moves = result.optimal_moves.or(daily.optimal_moves);
+        }
+    }
+
+    /// Starts and stops the solve timer in response to twists and keys.
+    fn update_timer(&mut self, ctx: &egui::Context) {
+        if !self.puzzle.was_scrambled() {
+            self.round = None;
+            self.daily = None;
+        }
+        if !(self.prefs.show_timer || self.round.is_some() || self.daily.is_some())
+            || !self.puzzle.was_scrambled()
+            || self.bld.is_some()
+            || self.fmc.is_some()
+            || self.trainer.is_some()
+        {
+            self.timer = SolveTimer::Idle;
+            return;
+        }
+        let was_stopped = matches!(self.timer, SolveTimer::Stopped(_));
+
+        let solved = self.puzzle.is_solved();
+        match self.prefs.timer_start {
+            TimerStart::FirstTwist => {
+                if !self.puzzle.history().is_empty() {
+                    self.timer.start();
+                }
+                if solved {
+                    self.timer.stop(true);
+                }
+            }
+            TimerStart::Stackmat => {
+                if !ctx.wants_keyboard_input() {
+                    if ctx.input(|input| input.key_pressed(egui::Key::Space)) {
+                        self.timer.press_space(solved);
+                    }
+                    if ctx.input(|input| input.key_released(egui::Key::Space)) {
+                        self.timer.release_space();
+                    }
+                }
+            }
+        }
+        self.timer.update();
+
+        if let (SolveTimer::Stopped(result), false) = (self.timer, was_stopped) {
+            let moves = Algorithm(self.puzzle.history().to_vec());
+            if let Some(daily) = &self.daily {
+                self.daily_results.entry(daily.day).or_insert(DailyResult {
+                    config: daily.config,
+                    result,
+                    moves: moves.simplified(daily.config).len(),
+                    optimal_moves: daily.optimal_moves,
+                });
+            }
+            if let Some(round) = &mut self.round {
+                round.solves.push(RoundSolve {
+                    result,
+                    moves: moves.to_string(),
+                });
+            }
+        }
+
+        if self.timer.is_running() {
+            ctx.request_repaint();
+        }
+    }
+
+    fn show_timer(&self, ui: &mut egui::Ui) {
+        let visuals = ui.visuals();
+        let (text, color) = match self.timer {
+            SolveTimer::Idle => return,
+            SolveTimer::Stopped(result) => (result.to_string(), visuals.strong_text_color()),
+            SolveTimer::Running { .. } => (
+                format_duration(self.timer.elapsed()),
+                visuals.strong_text_color(),
+            ),
+            SolveTimer::Ready { .. } => match self.timer.is_held_long_enough() {
+                Some(true) => ("Release to start".to_owned(), egui::Color32::GREEN),
+                Some(false) => ("Hold…".to_owned(), visuals.error_fg_color),
+                None => match self.timer.inspection_elapsed() {
+                    Some(elapsed) => {
+                        let warning = INSPECTION_WARNINGS
+                            .iter()
+                            .rposition(|&warning| elapsed >= warning);
+                        let color = match warning {
+                            None => visuals.strong_text_color(),
+                            Some(0) => visuals.warn_fg_color,
+                            Some(_) => visuals.error_fg_color,
+                        };
+                        let text = match (INSPECTION_TIME.checked_sub(elapsed), warning) {
+                            (None, _) => "+2".to_owned(),
+                            (Some(remaining), None) => {
+                                format!("Inspection {}", remaining.as_secs_f32().ceil())
+                            }
+                            (Some(remaining), Some(i)) => format!(
+                                "Inspection {} ({} seconds!)",
+                                remaining.as_secs_f32().ceil(),
+                                INSPECTION_WARNINGS[i].as_secs(),
+                            ),
+                        };
+                        (text, color)
+                    }
+                    None => (
+                        format_duration(self.timer.elapsed()),
+                        visuals.weak_text_color(),
+                    ),
+                },
+            },
+        };
+        ui.heading(egui::RichText::new(text).color(color).monospace());
+    }
+
     fn show_puzzle(&mut self, ui: &mut egui::Ui) {
         ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
-            if self.puzzle.was_scrambled() && self.puzzle.is_solved() {
-                ui.heading("Solved!");
+            if let Some(attempt) = &self.bld {
+                let phase = match attempt {
+                    BldAttempt::Memo { .. } => "Memo",
+                    BldAttempt::Execution { .. } => "Execution",
+                    BldAttempt::Done(result) if result.success => "Success",
+                    BldAttempt::Done(_) => "DNF",
+                };
+                ui.heading(format!("{phase} {}", format_duration(attempt.elapsed())));
+            } else if let Some(attempt) = &self.trainer {
+                let time = format_duration(attempt.elapsed());
+                ui.heading(match attempt.phase {
+                    TrainerPhase::Done(_) => format!("Goal done! {time}"),
+                    _ => time,
+                });
+            } else {
+                if self.puzzle.was_scrambled() && self.puzzle.is_solved() {
+                    ui.heading("Solved!");
+                }
+                self.show_timer(ui);
             }
             ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
                 ui.heading("Sphenic Biaxe Puzzle");
             });
         });
 
+        if self.prefs.show_progress && !self.puzzle.is_blindfolded() {
+            egui::TopBottomPanel::bottom("progress_panel")
+                .frame(egui::Frame::NONE)
+                .show_inside(ui, |ui| self.puzzle.show_progress(ui, &self.prefs));
+        }
+
         self.puzzle.show_puzzle(ui, &self.prefs);
     }
 }
@@ -133,6 +860,16 @@ impl eframe::App for App {
         let is_web = cfg!(target_arch = "wasm32");
         let is_landscape = ctx.available_rect().aspect_ratio() > 1.0;
 
+        self.update_bld(ctx);
+        self.update_trainer(ctx);
+        self.update_timer(ctx);
+        self.update_daily();
+        // In Stackmat mode, the solve must not start before the timer does.
+        self.puzzle.set_twists_locked(
+            self.prefs.timer_start == TimerStart::Stackmat
+                && matches!(self.timer, SolveTimer::Ready { .. }),
+        );
+
         if !is_web {
             egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
                 egui::menu::bar(ui, |ui| {
@@ -203,6 +940,25 @@ impl eframe::App for App {
     }
 }
 
+fn format_daily_result(result: &DailyResult) -> String {
+    let mut s = format!(
+        "{}: {} in {} moves",
+        result.config, result.result, result.moves
+    );
+    if let Some(optimal) = result.optimal_moves {
+        s += &format!(" (optimal {optimal})");
+    }
+    s
+}
+
+/// Returns a seed for a competition round, different each time.
+fn random_seed() -> u64 {
+    let mut h = std::hash::DefaultHasher::new();
+    std::hash::Hash::hash(&web_time::Instant::now(), &mut h);
+    // Keep seeds short enough to publish easily.
+    std::hash::Hasher::finish(&h) % 1_000_000
+}
+
 fn show_credits(ui: &mut egui::Ui) {
     ui.label(format!("Sphenic Biaxe v{} by ", env!("CARGO_PKG_VERSION")));
     ui.hyperlink_to("Andrew Farkas", "https://ajfarkas.dev/");
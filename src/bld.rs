@@ -0,0 +1,83 @@
+//! Blindfolded solving: memorize the scrambled puzzle, then solve it without
+//! seeing the pieces.
+
+use web_time::{Duration, Instant};
+
+/// Result of a blindfolded attempt.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct BldResult {
+    /// Time spent memorizing, with the pieces visible.
+    pub memo: Duration,
+    /// Time spent solving without seeing the pieces.
+    pub execution: Duration,
+    /// Whether the puzzle was solved at the end. An unsolved puzzle is a DNF
+    /// (did not finish).
+    pub success: bool,
+}
+impl BldResult {
+    pub fn total(&self) -> Duration {
+        self.memo + self.execution
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BldAttempt {
+    /// Memorizing the scrambled puzzle. Pieces are visible.
+    Memo { start: Instant },
+    /// Solving without seeing the pieces.
+    Execution { start: Instant, memo: Duration },
+    /// The attempt is over and the result has been revealed.
+    Done(BldResult),
+}
+impl BldAttempt {
+    /// Starts the memo phase of a new attempt.
+    pub fn start() -> Self {
+        Self::Memo {
+            start: Instant::now(),
+        }
+    }
+
+    /// Returns whether the pieces should be hidden.
+    pub fn is_blindfolded(&self) -> bool {
+        matches!(self, Self::Execution { .. })
+    }
+    /// Returns whether the timer is running.
+    pub fn is_running(&self) -> bool {
+        !matches!(self, Self::Done(_))
+    }
+
+    /// Ends the memo phase and hides the pieces.
+    pub fn put_on_blindfold(&mut self) {
+        if let Self::Memo { start } = *self {
+            *self = Self::Execution {
+                start: Instant::now(),
+                memo: start.elapsed(),
+            };
+        }
+    }
+
+    /// Ends the attempt, given whether the puzzle is solved.
+    pub fn finish(&mut self, solved: bool) {
+        *self = Self::Done(match *self {
+            Self::Memo { start } => BldResult {
+                memo: start.elapsed(),
+                execution: Duration::ZERO,
+                success: solved,
+            },
+            Self::Execution { start, memo } => BldResult {
+                memo,
+                execution: start.elapsed(),
+                success: solved,
+            },
+            Self::Done(result) => result,
+        });
+    }
+
+    /// Returns the time to display for the current phase.
+    pub fn elapsed(&self) -> Duration {
+        match self {
+            Self::Memo { start } | Self::Execution { start, .. } => start.elapsed(),
+            Self::Done(result) => result.total(),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Command-line interface for exporting puzzle drawings without opening a
+//! window.
+
+use std::io::Write;
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use sphenic_biaxe::export::{
+    AnimationFormat, AnimationOptions, SvgOptions, TemplateOptions, export_animation, export_png,
+    export_svg, export_template,
+};
+use sphenic_biaxe::{Algorithm, LabelScheme, PuzzleConfig, PuzzleState, Round};
+
+const USAGE: &str = "\
+Usage: sphenic_biaxe <COMMAND> [OPTIONS]
+       sphenic_biaxe verify <FILE>
+
+Commands:
+  svg       Export a drawing of the puzzle as SVG
+  png       Render the puzzle to a PNG image
+  anim      Render an algorithm as an animated GIF or APNG
+  template  Export a printable 1:1 cut template as SVG
+  verify    Check a competition round results file by replaying its solves
+
+Options:
+  --puzzle <PUZZLE>    Puzzle configuration, such as M5F2 [default: M5F2]
+  --scramble <SEED>    Draw a scrambled state from a numeric seed
+  --scale <SCALE>      Size of one unit of edge length in SVG [default: 100]
+  --width <WIDTH>      Width of PNG, GIF, or APNG in pixels [default: 800]
+  --edge-mm <MM>       Edge length of one piece in templates [default: 20]
+  --alg <ALG>          Algorithm to animate, such as \"L R2' L'\"
+  --format <FORMAT>    Animation format: gif or apng [default: gif]
+  --fps <FPS>          Animation frames per second [default: 30]
+  --twist-duration <SECONDS>
+                       Duration of each twist in animations [default: 0.5]
+  --no-captions        Hide the current twist in animations
+  --no-labels          Hide labels
+  --light              Use the light palette
+  -o, --output <FILE>  Write to a file instead of stdout
+";
+
+/// Arguments that start the command-line interface instead of the app.
+const COMMANDS: &[&str] = &[
+    "svg", "png", "anim", "template", "verify", "help", "-h", "--help",
+];
+
+/// Commands that export a file.
+enum Export {
+    Svg,
+    Png,
+    Anim,
+    Template,
+}
+
+/// Returns whether `arg` is a command of the command-line interface.
+pub fn is_command(arg: &str) -> bool {
+    COMMANDS.contains(&arg)
+}
+
+/// Runs the command-line interface with `args`, excluding the program name.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let mut args = args.iter();
+    let command = match args.next().map(String::as_str) {
+        None | Some("-h" | "--help" | "help") => {
+            print!("{USAGE}");
+            return Ok(());
+        }
+        Some("verify") => {
+            let path = args.next().ok_or("missing results file")?;
+            return verify_round(path);
+        }
+        Some("svg") => Export::Svg,
+        Some("png") => Export::Png,
+        Some("anim") => Export::Anim,
+        Some("template") => Export::Template,
+        Some(other) => return Err(format!("unknown command {other:?}\n\n{USAGE}")),
+    };
+
+    let mut config = PuzzleConfig::default();
+    let mut seed = None;
+    let mut svg_options = SvgOptions::default();
+    let mut anim_options = AnimationOptions::default();
+    let mut template_options = TemplateOptions::default();
+    let mut anim_format = AnimationFormat::Gif;
+    let mut alg = Algorithm::default();
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("missing value for {arg}"))
+        };
+        match arg.as_str() {
+            "--puzzle" => config = value()?.parse()?,
+            "--scramble" => seed = Some(parse_number::<u64>(arg, value()?)?),
+            "--scale" => svg_options.scale = parse_number(arg, value()?)?,
+            "--width" => anim_options.render.width = parse_number(arg, value()?)?,
+            "--edge-mm" => template_options.edge_length_mm = parse_number(arg, value()?)?,
+            "--alg" => alg = value()?.parse()?,
+            "--format" => {
+                anim_format = match value()?.as_str() {
+                    "gif" => AnimationFormat::Gif,
+                    "apng" => AnimationFormat::Apng,
+                    other => return Err(format!("unknown animation format {other:?}")),
+                }
+            }
+            "--fps" => anim_options.fps = parse_number(arg, value()?)?,
+            "--twist-duration" => anim_options.twist_duration = parse_number(arg, value()?)?,
+            "--no-captions" => anim_options.captions = false,
+            "--no-labels" => {
+                svg_options.labels = LabelScheme::hidden();
+                template_options.labels = LabelScheme::hidden();
+                anim_options.render.labels = LabelScheme::hidden();
+            }
+            "--light" => {
+                svg_options.dark_mode = false;
+                anim_options.render.dark_mode = false;
+            }
+            "-o" | "--output" => output = Some(value()?.clone()),
+            _ => return Err(format!("unknown option {arg:?}\n\n{USAGE}")),
+        }
+    }
+
+    let state = match seed {
+        Some(seed) => PuzzleState::random(config, &mut ChaCha8Rng::seed_from_u64(seed)),
+        None => PuzzleState::new(config),
+    };
+
+    let contents = match command {
+        Export::Svg => {
+            let s = svg_options.scale;
+            if !(s.is_finite() && s > 0.0) {
+                return Err("scale must be positive".to_string());
+            }
+            export_svg(config, &state, &svg_options).into_bytes()
+        }
+        Export::Png => {
+            export_png(config, &state, &anim_options.render).map_err(|e| e.to_string())?
+        }
+        Export::Anim => {
+            if !(anim_options.fps.is_finite() && anim_options.fps > 0.0) {
+                return Err("frames per second must be positive".to_string());
+            }
+            let d = anim_options.twist_duration;
+            if !(d.is_finite() && d > 0.0) {
+                return Err("twist duration must be positive".to_string());
+            }
+            export_animation(config, &state, &alg, &anim_options, anim_format)?
+        }
+        Export::Template => {
+            let e = template_options.edge_length_mm;
+            if !(e.is_finite() && e > 0.0) {
+                return Err("edge length must be positive".to_string());
+            }
+            export_template(config, &template_options).into_bytes()
+        }
+    };
+
+    match output {
+        Some(path) => std::fs::write(&path, contents).map_err(|e| format!("{path}: {e}")),
+        None => std::io::stdout()
+            .write_all(&contents)
+            .map_err(|e| e.to_string()),
+    }
+}
+
+/// Prints the results in a competition round results file after checking
+/// them.
+fn verify_round(path: &str) -> Result<(), String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?;
+    let round = Round::from_file(&contents).map_err(|e| format!("{path}: {e}"))?;
+    println!(
+        "{} round with seed {}, {} of {} solves",
+        round.config,
+        round.seed,
+        round.solves.len(),
+        round.count,
+    );
+    for (i, solve) in round.solves.iter().enumerate() {
+        let move_count = solve.moves.parse::<Algorithm>()?.len();
+        println!("{}. {} ({move_count} moves)", i + 1, solve.result);
+    }
+    if let Some(average) = round.average() {
+        println!("Average: {average}");
+    }
+    println!("All solves verified");
+    Ok(())
+}
+
+fn parse_number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid value {value:?} for {arg}"))
+}
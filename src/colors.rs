@@ -0,0 +1,303 @@
+//! Color schemes for sectors and stickers.
+
+use std::fmt;
+
+use egui::{Color32, Rgba};
+use serde::{Deserialize, Serialize};
+
+use crate::PuzzleConfig;
+
+/// Gradient that piece colors are sampled from.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Gradient {
+    /// Rainbow with the shared piece in red and, on puzzles with only two
+    /// sectors on the right disk, the other piece in gray.
+    #[default]
+    Classic,
+    Rainbow,
+    Sinebow,
+    Turbo,
+    Spectral,
+    Viridis,
+    Plasma,
+    Inferno,
+    Magma,
+    Cividis,
+    Warm,
+    Cool,
+    Cubehelix,
+    /// Blue to yellow, avoiding reds that look dark with protanopia.
+    Protanopia,
+    /// Blue, purple, and orange, which stay distinct with deuteranopia.
+    Deuteranopia,
+    /// Red to cyan, avoiding blue and yellow hues that are confused with
+    /// tritanopia.
+    Tritanopia,
+}
+
+impl fmt::Display for Gradient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Protanopia => write!(f, "Protanopia-safe"),
+            Self::Deuteranopia => write!(f, "Deuteranopia-safe"),
+            Self::Tritanopia => write!(f, "Tritanopia-safe"),
+            _ => fmt::Debug::fmt(self, f),
+        }
+    }
+}
+
+impl Gradient {
+    pub const ALL: [Self; 16] = [
+        Self::Classic,
+        Self::Rainbow,
+        Self::Sinebow,
+        Self::Turbo,
+        Self::Spectral,
+        Self::Viridis,
+        Self::Plasma,
+        Self::Inferno,
+        Self::Magma,
+        Self::Cividis,
+        Self::Warm,
+        Self::Cool,
+        Self::Cubehelix,
+        Self::Protanopia,
+        Self::Deuteranopia,
+        Self::Tritanopia,
+    ];
+
+    fn colorous(self) -> Option<colorous::Gradient> {
+        Some(match self {
+            Self::Classic | Self::Rainbow => colorous::RAINBOW,
+            Self::Sinebow => colorous::SINEBOW,
+            Self::Turbo => colorous::TURBO,
+            Self::Spectral => colorous::SPECTRAL,
+            Self::Viridis => colorous::VIRIDIS,
+            Self::Plasma => colorous::PLASMA,
+            Self::Inferno => colorous::INFERNO,
+            Self::Magma => colorous::MAGMA,
+            Self::Cividis => colorous::CIVIDIS,
+            Self::Warm => colorous::WARM,
+            Self::Cool => colorous::COOL,
+            Self::Cubehelix => colorous::CUBEHELIX,
+            Self::Protanopia | Self::Deuteranopia | Self::Tritanopia => return None,
+        })
+    }
+
+    /// Returns the colors that the gradient interpolates between, for
+    /// gradients that are not provided by `colorous`.
+    fn stops(self) -> &'static [[u8; 3]] {
+        match self {
+            Self::Protanopia => &[
+                [0x00, 0x44, 0x88],
+                [0x33, 0x99, 0xee],
+                [0xaa, 0xcc, 0xff],
+                [0xff, 0xee, 0x66],
+                [0xdd, 0xaa, 0x33],
+                [0x77, 0x55, 0x00],
+            ],
+            Self::Deuteranopia => &[
+                [0x00, 0x72, 0xb2],
+                [0x56, 0xb4, 0xe9],
+                [0xcc, 0x79, 0xa7],
+                [0xf0, 0xe4, 0x42],
+                [0xe6, 0x9f, 0x00],
+                [0x99, 0x4f, 0x00],
+            ],
+            Self::Tritanopia => &[
+                [0x8b, 0x00, 0x00],
+                [0xe0, 0x50, 0x50],
+                [0xff, 0xb0, 0xb0],
+                [0x80, 0xe0, 0xe0],
+                [0x00, 0x8b, 0x8b],
+                [0x00, 0x48, 0x48],
+            ],
+            _ => &[],
+        }
+    }
+
+    /// Returns the color of piece `i`, with its lightness scaled by
+    /// `brightness`.
+    fn color(self, config: PuzzleConfig, i: u32, brightness: f32, dark_mode: bool) -> Color32 {
+        if self != Self::Classic {
+            return shade(self.sample(i, config.piece_count()), brightness * 0.5);
+        }
+
+        let shared_color = || {
+            let lightness = match () {
+                _ if config.b == 2 => 0.5,
+                _ if dark_mode => 0.45,
+                _ => 0.65,
+            };
+            shade(self.sample(0, 1), brightness * lightness)
+        };
+        if i == 0 {
+            shared_color()
+        } else if i < config.a {
+            shade(self.sample(config.a - i, config.a), brightness * 0.5)
+        } else if config.b == 2 {
+            Color32::DARK_GRAY
+        } else {
+            let lightness = if dark_mode { 0.25 } else { 0.75 };
+            let j = i - config.a + config.shared;
+            shade(self.sample(j, config.b), brightness * lightness)
+        }
+    }
+
+    /// Samples the gradient with `n` colors at index `i`.
+    fn sample(self, i: u32, n: u32) -> Color32 {
+        if let Some(gradient) = self.colorous() {
+            let colorous::Color { r, g, b } = gradient.eval_rational(i as usize, n as usize);
+            return Color32::from_rgb(r, g, b);
+        }
+
+        let stops = self.stops();
+        let t = match n {
+            0 | 1 => 0.0,
+            _ => i as f32 / (n - 1) as f32 * (stops.len() - 1) as f32,
+        };
+        let k = (t as usize).min(stops.len() - 2);
+        let [r, g, b] = [0, 1, 2].map(|c| {
+            crate::util::lerp(stops[k][c] as f32, stops[k + 1][c] as f32, t - k as f32).round()
+                as u8
+        });
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+/// Colors for one theme.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct Palette {
+    pub gradient: Gradient,
+    /// Sticker colors chosen by the user, indexed by piece, that override the
+    /// gradient.
+    pub piece_colors: Vec<Option<Color32>>,
+}
+
+impl Palette {
+    /// Returns the color chosen by the user for piece `i`, if any.
+    pub fn piece_color(&self, i: u32) -> Option<Color32> {
+        self.piece_colors.get(i as usize).copied().flatten()
+    }
+    /// Sets or clears the color chosen by the user for piece `i`.
+    pub fn set_piece_color(&mut self, i: u32, color: Option<Color32>) {
+        let i = i as usize;
+        if i >= self.piece_colors.len() {
+            self.piece_colors.resize(i + 1, None);
+        }
+        self.piece_colors[i] = color;
+        while self.piece_colors.last() == Some(&None) {
+            self.piece_colors.pop();
+        }
+    }
+
+    fn color(&self, config: PuzzleConfig, i: u32, brightness: f32, dark_mode: bool) -> Color32 {
+        match self.piece_color(i) {
+            Some(color) => shade(color, brightness / sticker_brightness(dark_mode) * 0.5),
+            None => self.gradient.color(config, i, brightness, dark_mode),
+        }
+    }
+}
+
+/// Colors for sectors and stickers, separately for dark and light mode.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct ColorScheme {
+    pub dark: Palette,
+    pub light: Palette,
+}
+
+impl ColorScheme {
+    pub fn palette(&self, dark_mode: bool) -> &Palette {
+        match dark_mode {
+            true => &self.dark,
+            false => &self.light,
+        }
+    }
+    pub fn palette_mut(&mut self, dark_mode: bool) -> &mut Palette {
+        match dark_mode {
+            true => &mut self.dark,
+            false => &mut self.light,
+        }
+    }
+
+    pub fn sticker_color(&self, config: PuzzleConfig, i: u32, dark_mode: bool) -> Color32 {
+        self.palette(dark_mode)
+            .color(config, i, sticker_brightness(dark_mode), dark_mode)
+    }
+    pub fn sector_color(&self, config: PuzzleConfig, i: u32, dark_mode: bool) -> Color32 {
+        self.palette(dark_mode).color(config, i, 0.9, dark_mode)
+    }
+}
+
+/// Type of color vision deficiency, for previewing color schemes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ColorDeficiency {
+    Protanopia,
+    Deuteranopia,
+    Tritanopia,
+}
+
+impl fmt::Display for ColorDeficiency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl ColorDeficiency {
+    pub const ALL: [Self; 3] = [Self::Protanopia, Self::Deuteranopia, Self::Tritanopia];
+
+    /// Returns how `color` appears with this deficiency, using the model by
+    /// Machado, Oliveira, and Fernandes (2009) at full severity.
+    pub fn simulate(self, color: Color32) -> Color32 {
+        let m = match self {
+            Self::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            Self::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            Self::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        };
+        let c = Rgba::from(color);
+        let [r, g, b] =
+            m.map(|row| (row[0] * c.r() + row[1] * c.g() + row[2] * c.b()).clamp(0.0, 1.0));
+        Color32::from(Rgba::from_rgba_premultiplied(r, g, b, c.a()))
+    }
+}
+
+/// Returns black or white, whichever is more legible on top of `background`.
+pub fn contrasting_color(background: Color32) -> Color32 {
+    let c = Rgba::from(background);
+    let luminance = 0.2126 * c.r() + 0.7152 * c.g() + 0.0722 * c.b();
+    match luminance > 0.18 {
+        true => Color32::BLACK,
+        false => Color32::WHITE,
+    }
+}
+
+/// Returns the brightness of stickers, relative to which colors chosen by the
+/// user are shaded.
+fn sticker_brightness(dark_mode: bool) -> f32 {
+    if dark_mode { 1.0 } else { 0.85 }
+}
+
+/// Blends `color` toward white or black. `lightness` ranges from 0 to 1, with
+/// 0.5 leaving the color unchanged.
+fn shade(color: Color32, lightness: f32) -> Color32 {
+    let blend_color = if lightness > 0.5 {
+        Color32::WHITE.gamma_multiply(lightness * 2.0 - 1.0)
+    } else {
+        Color32::BLACK.gamma_multiply(1.0 - lightness * 2.0)
+    };
+    color.blend(blend_color)
+}
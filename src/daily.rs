@@ -0,0 +1,78 @@
+//! Daily challenge: a puzzle and scramble derived from the date, the same for
+//! everyone.
+
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::timer::SolveResult;
+use crate::{PuzzleConfig, PuzzleState};
+
+/// Puzzles used for daily challenges. All of them are small enough for
+/// [`DistanceTables`](crate::DistanceTables) so that the optimal move count
+/// can be shown. Changing this list changes the challenge for every day.
+const DAILY_PUZZLES: &[&str] = &[
+    "M4F2", "M5F2", "M6F2", "M5M2", "M6M2", "M4M3", "M5M3", "M4M4", "M5F3", "F5M3",
+];
+
+/// Returns the current day, counted in UTC from 1970-01-01.
+pub fn today() -> u32 {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    (secs / 86_400) as u32
+}
+
+/// Formats a day counted from 1970-01-01 as `YYYY-MM-DD`.
+pub fn format_day(day: u32) -> String {
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[derive(Debug, Clone)]
+pub struct DailyChallenge {
+    /// Day counted from 1970-01-01.
+    pub day: u32,
+    pub config: PuzzleConfig,
+    pub scramble: PuzzleState,
+    /// Optimal number of moves to solve the scramble, once its distance
+    /// table has been computed.
+    pub optimal_moves: Option<u32>,
+}
+
+impl DailyChallenge {
+    pub fn for_day(day: u32) -> Self {
+        // `StdRng` may change between versions of `rand`, which would change
+        // past and future challenges.
+        let mut rng = ChaCha8Rng::seed_from_u64(day as u64);
+        let puzzle = DAILY_PUZZLES[rng.random_range(0..DAILY_PUZZLES.len())];
+        let config = puzzle.parse().unwrap_or_default();
+        let scramble = PuzzleState::random(config, &mut rng);
+        Self {
+            day,
+            config,
+            scramble,
+            optimal_moves: None,
+        }
+    }
+}
+
+/// Result of the first attempt at a daily challenge.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+pub struct DailyResult {
+    pub config: PuzzleConfig,
+    pub result: SolveResult,
+    /// Number of moves used, counted the same way as the optimal solution.
+    pub moves: usize,
+    pub optimal_moves: Option<u32>,
+}
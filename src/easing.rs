@@ -0,0 +1,78 @@
+//! Easing curves for twist animations.
+
+use std::f32::consts::PI;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// Curve that maps the progress of a twist animation to the fraction of the
+/// twist that has been rotated.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Easing {
+    /// Constant speed.
+    Linear,
+    /// Starts and ends slowly, following half a cosine wave.
+    #[default]
+    Cosine,
+    /// Starts and ends more slowly than [`Easing::Cosine`].
+    Cubic,
+    /// Rotates slightly past the target and settles back.
+    Overshoot,
+    /// Bounces against the target a few times.
+    Bounce,
+}
+
+impl fmt::Display for Easing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl Easing {
+    pub const ALL: [Self; 5] = [
+        Self::Linear,
+        Self::Cosine,
+        Self::Cubic,
+        Self::Overshoot,
+        Self::Bounce,
+    ];
+
+    /// Returns the fraction of the twist completed at progress `t`, from 0.0
+    /// to 1.0. The result is 0.0 at the start and 1.0 at the end, but may
+    /// leave that range in between.
+    pub fn apply(self, t: f32) -> f32 {
+        match self {
+            Self::Linear => t,
+            Self::Cosine => (1.0 - (t * PI).cos()) / 2.0,
+            Self::Cubic => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (2.0 - 2.0 * t).powi(3) / 2.0
+                }
+            }
+            Self::Overshoot => {
+                const C1: f32 = 1.70158;
+                const C3: f32 = C1 + 1.0;
+                let u = t - 1.0;
+                1.0 + C3 * u.powi(3) + C1 * u.powi(2)
+            }
+            Self::Bounce => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let u = t - 1.5 / D;
+                    N * u * u + 0.75
+                } else if t < 2.5 / D {
+                    let u = t - 2.25 / D;
+                    N * u * u + 0.9375
+                } else {
+                    let u = t - 2.625 / D;
+                    N * u * u + 0.984375
+                }
+            }
+        }
+    }
+}
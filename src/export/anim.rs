@@ -0,0 +1,137 @@
+//! Animated GIF and APNG export of twist sequences.
+
+use egui::ColorImage;
+
+use super::RenderOptions;
+use super::png::Renderer;
+use crate::{Algorithm, PuzzleConfig, PuzzleState, PuzzleView};
+
+/// Number of seconds to show the first and last frames.
+const HOLD_DURATION: f32 = 1.0;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    pub render: RenderOptions,
+    /// Frames per second.
+    pub fps: f32,
+    /// Number of seconds per twist.
+    pub twist_duration: f32,
+    /// Whether to show the current twist below the puzzle.
+    pub captions: bool,
+}
+
+impl Default for AnimationOptions {
+    fn default() -> Self {
+        Self {
+            render: RenderOptions::default(),
+            fps: 30.0,
+            twist_duration: 0.5,
+            captions: true,
+        }
+    }
+}
+
+/// Frame of an animation.
+pub struct AnimationFrame {
+    pub image: ColorImage,
+    /// Number of seconds to show the frame.
+    pub duration: f32,
+}
+
+/// Renders each frame of `alg` applied to `start`. Twists are interpolated the
+/// same way as in the app.
+pub fn render_animation(
+    config: PuzzleConfig,
+    start: &PuzzleState,
+    alg: &Algorithm,
+    options: &AnimationOptions,
+) -> Vec<AnimationFrame> {
+    let mut renderer = Renderer::default();
+    let mut view = PuzzleView::new(config, start.clone());
+    view.freeze_animation();
+    let mut render = |view: &mut PuzzleView, caption: String, duration: f32| AnimationFrame {
+        image: renderer.render(view, &options.render, options.captions.then_some(&caption)),
+        duration,
+    };
+
+    let frames_per_twist = (options.twist_duration * options.fps).round().max(1.0) as usize;
+    let mut frames = vec![render(&mut view, String::new(), HOLD_DURATION)];
+    for (i, &twist) in alg.0.iter().enumerate() {
+        let caption = format!("{}/{}: {twist}", i + 1, alg.len());
+        view.apply_twist(twist);
+        for k in 0..frames_per_twist {
+            view.set_twist_progress(k as f32 / frames_per_twist as f32);
+            frames.push(render(&mut view, caption.clone(), 1.0 / options.fps));
+        }
+        view.skip_animations();
+    }
+    frames.push(render(&mut view, String::new(), HOLD_DURATION));
+    frames
+}
+
+/// Renders `alg` applied to `start` and encodes it as an animated image.
+pub fn export_animation(
+    config: PuzzleConfig,
+    start: &PuzzleState,
+    alg: &Algorithm,
+    options: &AnimationOptions,
+    format: AnimationFormat,
+) -> Result<Vec<u8>, String> {
+    let frames = render_animation(config, start, alg, options);
+    match format {
+        AnimationFormat::Gif => encode_gif(&frames),
+        AnimationFormat::Apng => encode_apng(&frames).map_err(|e| e.to_string()),
+    }
+}
+
+pub fn encode_gif(frames: &[AnimationFrame]) -> Result<Vec<u8>, String> {
+    let Some(first) = frames.first() else {
+        return Err("no frames".to_string());
+    };
+    let [w, h] = first
+        .image
+        .size
+        .map(|x| u16::try_from(x).map_err(|_| "image too large"));
+    let (w, h) = (w?, h?);
+
+    let mut bytes = vec![];
+    let mut encoder = gif::Encoder::new(&mut bytes, w, h, &[]).map_err(|e| e.to_string())?;
+    encoder
+        .set_repeat(gif::Repeat::Infinite)
+        .map_err(|e| e.to_string())?;
+    for frame in frames {
+        let mut rgba = super::rgba_bytes(&frame.image);
+        let mut gif_frame = gif::Frame::from_rgba_speed(w, h, &mut rgba, 10);
+        // GIF delays are in hundredths of a second.
+        gif_frame.delay = (frame.duration * 100.0).round().max(1.0) as u16;
+        encoder.write_frame(&gif_frame).map_err(|e| e.to_string())?;
+    }
+    drop(encoder);
+    Ok(bytes)
+}
+
+pub fn encode_apng(frames: &[AnimationFrame]) -> Result<Vec<u8>, png::EncodingError> {
+    let [w, h] = frames.first().map_or([0, 0], |f| f.image.size);
+
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, w as u32, h as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        let millis = (frame.duration * 1000.0)
+            .round()
+            .clamp(1.0, u16::MAX as f32) as u16;
+        writer.set_frame_delay(millis, 1000)?;
+        writer.write_image_data(&super::rgba_bytes(&frame.image))?;
+    }
+    writer.finish()?;
+    Ok(bytes)
+}
@@ -0,0 +1,26 @@
+//! Exporting puzzle drawings to files.
+
+use egui::ColorImage;
+
+mod anim;
+mod png;
+mod raster;
+mod svg;
+mod template;
+
+pub use anim::{
+    AnimationFormat, AnimationFrame, AnimationOptions, encode_apng, encode_gif, export_animation,
+    render_animation,
+};
+pub use png::{RenderOptions, encode_png, export_png, render_image};
+pub use svg::{SvgOptions, export_svg};
+pub use template::{TemplateOptions, export_template};
+
+/// Returns the pixels of an image as unmultiplied RGBA bytes.
+fn rgba_bytes(image: &ColorImage) -> Vec<u8> {
+    image
+        .pixels
+        .iter()
+        .flat_map(|c| c.to_srgba_unmultiplied())
+        .collect()
+}
@@ -0,0 +1,134 @@
+//! Headless raster rendering, using the same drawing code as the app.
+
+use egui::{ColorImage, Context, RawInput, Rect, Theme, Vec2, ViewportId, ViewportInfo};
+
+use super::raster::Rasterizer;
+use crate::{ColorScheme, LabelScheme, Preferences, PuzzleConfig, PuzzleState, PuzzleView};
+
+/// Zoom factor used by the app, so that labels are the same size relative to
+/// the puzzle.
+const ZOOM_FACTOR: f32 = 1.25;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    /// Width of the image in pixels. The height is determined by the aspect
+    /// ratio of the puzzle.
+    pub width: u32,
+    pub labels: LabelScheme,
+    pub dark_mode: bool,
+    pub colors: ColorScheme,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            labels: LabelScheme::default(),
+            dark_mode: true,
+            colors: ColorScheme::default(),
+        }
+    }
+}
+
+/// Renders `state` to an image.
+pub fn render_image(
+    config: PuzzleConfig,
+    state: &PuzzleState,
+    options: &RenderOptions,
+) -> ColorImage {
+    let mut view = PuzzleView::new(config, state.clone());
+    view.freeze_animation();
+    Renderer::default().render(&mut view, options, None)
+}
+
+/// Headless renderer for puzzle views. Reusing one renderer for several
+/// frames avoids rebuilding the font atlas each time.
+#[derive(Default)]
+pub(crate) struct Renderer {
+    ctx: Context,
+    rasterizer: Rasterizer,
+}
+
+impl Renderer {
+    /// Renders a puzzle view, including any twist animation in progress, to
+    /// an image, with an optional caption below the puzzle. The animation
+    /// should be frozen with [`PuzzleView::freeze_animation()`] so that it
+    /// is drawn exactly as set up.
+    pub fn render(
+        &mut self,
+        view: &mut PuzzleView,
+        options: &RenderOptions,
+        caption: Option<&str>,
+    ) -> ColorImage {
+        let prefs = Preferences {
+            labels: options.labels.clone(),
+            colors: options.colors.clone(),
+            ..Default::default()
+        };
+
+        let aspect_ratio = view.config().size().y / view.config().size().x;
+        let size_px = [
+            options.width as usize,
+            (options.width as f32 * aspect_ratio).round() as usize,
+        ];
+        let screen_size = Vec2::new(size_px[0] as f32, size_px[1] as f32) / ZOOM_FACTOR;
+
+        let ctx = &self.ctx;
+        ctx.set_theme(match options.dark_mode {
+            true => Theme::Dark,
+            false => Theme::Light,
+        });
+        let viewport = ViewportInfo {
+            native_pixels_per_point: Some(ZOOM_FACTOR),
+            ..Default::default()
+        };
+        let input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Default::default(), screen_size)),
+            viewports: std::iter::once((ViewportId::ROOT, viewport)).collect(),
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default()
+                .frame(egui::Frame::central_panel(&ctx.style()).inner_margin(0.0))
+                .show(ctx, |ui| {
+                    if let Some(caption) = caption {
+                        egui::TopBottomPanel::bottom("caption")
+                            .show_separator_line(false)
+                            .frame(egui::Frame::NONE)
+                            .show_inside(ui, |ui| {
+                                ui.vertical_centered(|ui| ui.heading(caption));
+                            });
+                    }
+                    view.show_puzzle(ui, &prefs);
+                });
+        });
+
+        self.rasterizer.update_textures(&output.textures_delta);
+        let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+        let mut image = ColorImage::new(size_px, ctx.style().visuals.panel_fill);
+        self.rasterizer
+            .paint(&mut image, &primitives, output.pixels_per_point);
+        image
+    }
+}
+
+/// Encodes an image as PNG.
+pub fn encode_png(image: &ColorImage) -> Result<Vec<u8>, png::EncodingError> {
+    let mut bytes = vec![];
+    let mut encoder = png::Encoder::new(&mut bytes, image.size[0] as u32, image.size[1] as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&super::rgba_bytes(image))?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+/// Renders `state` and encodes it as PNG.
+pub fn export_png(
+    config: PuzzleConfig,
+    state: &PuzzleState,
+    options: &RenderOptions,
+) -> Result<Vec<u8>, png::EncodingError> {
+    encode_png(&render_image(config, state, options))
+}
@@ -0,0 +1,177 @@
+//! Software rasterizer for the meshes produced by egui's tessellator, used to
+//! render the puzzle without a window or GPU.
+
+use std::collections::HashMap;
+
+use egui::epaint::textures::TexturesDelta;
+use egui::epaint::{ClippedPrimitive, ImageData, Primitive, TextureId, Vertex};
+use egui::{Color32, ColorImage, Pos2, Rect};
+
+#[derive(Debug, Default)]
+pub struct Rasterizer {
+    textures: HashMap<TextureId, ColorImage>,
+}
+
+impl Rasterizer {
+    /// Applies texture updates from egui. Call this before painting.
+    pub fn update_textures(&mut self, delta: &TexturesDelta) {
+        for (id, image_delta) in &delta.set {
+            let image = match &image_delta.image {
+                ImageData::Color(image) => ColorImage::clone(image),
+                ImageData::Font(font) => ColorImage {
+                    size: font.size,
+                    pixels: font.srgba_pixels(None).collect(),
+                },
+            };
+            match image_delta.pos {
+                None => {
+                    self.textures.insert(*id, image);
+                }
+                Some([x0, y0]) => {
+                    let Some(texture) = self.textures.get_mut(id) else {
+                        log::warn!("partial update to missing texture {id:?}");
+                        continue;
+                    };
+                    let [w, h] = image.size;
+                    for y in 0..h {
+                        for x in 0..w {
+                            texture[(x0 + x, y0 + y)] = image[(x, y)];
+                        }
+                    }
+                }
+            }
+        }
+        for id in &delta.free {
+            self.textures.remove(id);
+        }
+    }
+
+    /// Paints tessellated primitives onto `target`, blending in gamma space
+    /// with premultiplied alpha like egui's own renderers.
+    pub fn paint(
+        &self,
+        target: &mut ColorImage,
+        primitives: &[ClippedPrimitive],
+        pixels_per_point: f32,
+    ) {
+        let [w, h] = target.size;
+        let target_rect = Rect::from_min_max(Pos2::ZERO, Pos2::new(w as f32, h as f32));
+
+        for primitive in primitives {
+            let Primitive::Mesh(mesh) = &primitive.primitive else {
+                continue; // Paint callbacks are not supported.
+            };
+            let Some(texture) = self.textures.get(&mesh.texture_id) else {
+                log::warn!("missing texture {:?}", mesh.texture_id);
+                continue;
+            };
+            let clip_rect = Rect::from_min_max(
+                primitive.clip_rect.min * pixels_per_point,
+                primitive.clip_rect.max * pixels_per_point,
+            )
+            .intersect(target_rect);
+
+            for tri in mesh.indices.chunks_exact(3) {
+                let [a, b, c] = [0, 1, 2].map(|i| {
+                    let v = mesh.vertices[tri[i] as usize];
+                    Vertex {
+                        pos: (v.pos.to_vec2() * pixels_per_point).to_pos2(),
+                        ..v
+                    }
+                });
+                fill_triangle(target, texture, clip_rect, [a, b, c]);
+            }
+        }
+    }
+}
+
+fn fill_triangle(target: &mut ColorImage, texture: &ColorImage, clip_rect: Rect, v: [Vertex; 3]) {
+    let area = edge(v[0].pos, v[1].pos, v[2].pos);
+    if area == 0.0 {
+        return;
+    }
+    // Normalize the winding so that the edge functions are positive inside.
+    let v = if area < 0.0 { [v[0], v[2], v[1]] } else { v };
+    let area = area.abs();
+
+    let bounds = Rect::from_points(&[v[0].pos, v[1].pos, v[2].pos]).intersect(clip_rect);
+    if bounds.is_negative() {
+        return;
+    }
+    let (x0, x1) = (bounds.min.x.floor() as usize, bounds.max.x.ceil() as usize);
+    let (y0, y1) = (bounds.min.y.floor() as usize, bounds.max.y.ceil() as usize);
+
+    for y in y0..y1.min(target.size[1]) {
+        for x in x0..x1.min(target.size[0]) {
+            let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+            if !clip_rect.contains(p) {
+                continue;
+            }
+            let w = [
+                edge(v[1].pos, v[2].pos, p),
+                edge(v[2].pos, v[0].pos, p),
+                edge(v[0].pos, v[1].pos, p),
+            ];
+            let owns = |i: usize| {
+                let (a, b) = (v[(i + 1) % 3].pos, v[(i + 2) % 3].pos);
+                w[i] > 0.0 || w[i] == 0.0 && is_top_left(a, b)
+            };
+            if !(owns(0) && owns(1) && owns(2)) {
+                continue;
+            }
+            let w = w.map(|w| w / area);
+
+            let mut src = [0.0; 4];
+            let mut uv = Pos2::ZERO;
+            for i in 0..3 {
+                for (c, &vc) in src.iter_mut().zip(&v[i].color.to_array()) {
+                    *c += vc as f32 * w[i];
+                }
+                uv += v[i].uv.to_vec2() * w[i];
+            }
+            let texel = sample_bilinear(texture, uv);
+            let src = [0, 1, 2, 3].map(|i| src[i] * texel[i] / 255.0);
+
+            let dst = &mut target[(x, y)];
+            let inv_alpha = 1.0 - src[3] / 255.0;
+            let [r, g, b, a] = [0, 1, 2, 3].map(|i| {
+                (src[i] + dst.to_array()[i] as f32 * inv_alpha)
+                    .round()
+                    .clamp(0.0, 255.0) as u8
+            });
+            *dst = Color32::from_rgba_premultiplied(r, g, b, a);
+        }
+    }
+}
+
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Returns whether the edge from `a` to `b` is a top or left edge, so that
+/// pixels exactly on an edge shared by two triangles are only painted once.
+fn is_top_left(a: Pos2, b: Pos2) -> bool {
+    (a.y == b.y && b.x < a.x) || b.y > a.y
+}
+
+fn sample_bilinear(texture: &ColorImage, uv: Pos2) -> [f32; 4] {
+    let [w, h] = texture.size;
+    let x = (uv.x * w as f32 - 0.5).clamp(0.0, (w - 1) as f32);
+    let y = (uv.y * h as f32 - 0.5).clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (tx, ty) = (x.fract(), y.fract());
+
+    let mut ret = [0.0; 4];
+    for (px, py, weight) in [
+        (x0, y0, (1.0 - tx) * (1.0 - ty)),
+        (x1, y0, tx * (1.0 - ty)),
+        (x0, y1, (1.0 - tx) * ty),
+        (x1, y1, tx * ty),
+    ] {
+        for (c, &tc) in ret.iter_mut().zip(&texture[(px, py)].to_array()) {
+            *c += tc as f32 * weight;
+        }
+    }
+    ret
+}
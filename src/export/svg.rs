@@ -0,0 +1,227 @@
+//! SVG export using the same geometry as `PuzzleView::draw_grip()`, but with
+//! true circular arcs instead of polygons.
+
+use std::fmt::Write;
+
+use egui::{Color32, Vec2, Visuals, vec2};
+
+use crate::util::rotate_point;
+use crate::{ColorScheme, Grip, LabelScheme, PuzzleConfig, PuzzleState};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgOptions {
+    /// Number of SVG units per unit of edge length.
+    pub scale: f32,
+    pub labels: LabelScheme,
+    pub dark_mode: bool,
+    pub colors: ColorScheme,
+}
+
+impl Default for SvgOptions {
+    fn default() -> Self {
+        Self {
+            scale: 100.0,
+            labels: LabelScheme::default(),
+            dark_mode: true,
+            colors: ColorScheme::default(),
+        }
+    }
+}
+
+/// Returns an SVG document showing `state`.
+pub fn export_svg(config: PuzzleConfig, state: &PuzzleState, options: &SvgOptions) -> String {
+    let visuals = match options.dark_mode {
+        true => Visuals::dark(),
+        false => Visuals::light(),
+    };
+    let mut svg = SvgWriter {
+        config,
+        state,
+        options,
+        visuals,
+        out: String::new(),
+    };
+
+    let size = config.size() * options.scale;
+    let _ = writeln!(
+        svg.out,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w:.1}" height="{h:.1}" viewBox="0 0 {w:.1} {h:.1}">"#,
+        w = size.x,
+        h = size.y,
+    );
+    svg.write_grip(Grip::A, false);
+    svg.write_grip(Grip::B, true);
+    for grip in [Grip::A, Grip::B] {
+        svg.write_grip_circle(grip);
+    }
+    svg.out.push_str("</svg>\n");
+
+    svg.out
+}
+
+struct SvgWriter<'a> {
+    config: PuzzleConfig,
+    state: &'a PuzzleState,
+    options: &'a SvgOptions,
+    visuals: Visuals,
+    out: String,
+}
+
+impl SvgWriter<'_> {
+    fn write_grip(&mut self, grip: Grip, draw_intersection: bool) {
+        let cfg = self.config;
+        let dark_mode = self.options.dark_mode;
+        let center = cfg.center(grip);
+        let n = cfg.n(grip);
+
+        if cfg.axis_stationary(grip) {
+            let fill = paint("fill", self.visuals.code_bg_color);
+            let _ = writeln!(
+                self.out,
+                r#"<circle cx="{:.3}" cy="{:.3}" r="{:.3}" {fill}/>"#,
+                center.x * self.options.scale,
+                center.y * self.options.scale,
+                cfg.radius(grip) * self.options.scale,
+            );
+        }
+
+        // Sectors
+        let sign = match grip {
+            Grip::A => 1.0,
+            Grip::B => -1.0,
+        };
+        let sector_radius = cfg.sector_radius(grip);
+        let half_angle = std::f32::consts::PI / n as f32;
+        for i in 0..n {
+            let mut j = i;
+            if !cfg.axis_stationary(grip) {
+                j = (j + self.state.rot(grip)) % n;
+            }
+            let angle = cfg.slot_angle(grip, i);
+            let edge = center + vec2(sector_radius * sign, 0.0);
+            let p1 = self.transform(rotate_point(edge, center, angle - half_angle));
+            let p2 = self.transform(rotate_point(edge, center, angle + half_angle));
+            let c = self.transform(center);
+            let r = sector_radius * self.options.scale;
+            let color_index = cfg.color_index_in_grip(grip, j);
+            let color = self
+                .options
+                .colors
+                .sector_color(cfg, color_index, dark_mode);
+            let _ = writeln!(
+                self.out,
+                r#"<path d="M {:.3} {:.3} L {:.3} {:.3} A {r:.3} {r:.3} 0 0 1 {:.3} {:.3} Z" {}/>"#,
+                c.x,
+                c.y,
+                p1.x,
+                p1.y,
+                p2.x,
+                p2.y,
+                paint("fill", color),
+            );
+            if self.options.labels.show_sector_labels {
+                let pos = rotate_point(cfg.sector_label_pos(grip), center, angle);
+                let name = self.options.labels.sector_name(cfg, grip, j);
+                self.write_label(&name, pos);
+            }
+        }
+
+        // Sphenes
+        let (ra, rb) = (
+            cfg.radius(Grip::A) * self.options.scale,
+            cfg.radius(Grip::B) * self.options.scale,
+        );
+        let stroke = paint("stroke", self.visuals.strong_text_color());
+        let stroke_width = 0.005 * self.options.scale;
+        for i in 0..n {
+            if cfg.shared_slot(i).is_some() && !draw_intersection {
+                continue;
+            }
+            let angle = cfg.slot_angle(grip, i);
+            let sphene_center = cfg.sphene_center(grip);
+            let top = self.transform(rotate_point(sphene_center - vec2(0.0, 0.5), center, angle));
+            let bottom =
+                self.transform(rotate_point(sphene_center + vec2(0.0, 0.5), center, angle));
+            let sticker = self.state.pieces(grip)[i as usize];
+            let fill = paint(
+                "fill",
+                self.options.colors.sticker_color(cfg, sticker, dark_mode),
+            );
+            let _ = writeln!(
+                self.out,
+                r#"<path d="{}" {fill} {stroke} stroke-width="{stroke_width:.3}"/>"#,
+                lens_path(top, bottom, ra, rb),
+            );
+            if self.options.labels.show_sticker_labels {
+                let pos = rotate_point(sphene_center, center, angle);
+                let name = self.options.labels.sticker_name(cfg, sticker);
+                self.write_label(&name, pos);
+            }
+        }
+    }
+
+    fn write_grip_circle(&mut self, grip: Grip) {
+        let width = 0.01 * self.options.scale;
+        let c = self.transform(self.config.center(grip));
+        let _ = writeln!(
+            self.out,
+            r#"<circle cx="{:.3}" cy="{:.3}" r="{:.3}" fill="none" {} stroke-width="{width:.3}"/>"#,
+            c.x,
+            c.y,
+            self.config.radius(grip) * self.options.scale - width / 2.0,
+            paint("stroke", self.visuals.strong_text_color()),
+        );
+    }
+
+    fn write_label(&mut self, text: &str, pos: Vec2) {
+        let pos = self.transform(pos);
+        let labels = &self.options.labels;
+        let font_size = 0.15 * self.options.scale * labels.size;
+        let _ = writeln!(
+            self.out,
+            r#"<text x="{:.3}" y="{:.3}" font-family="sans-serif" font-size="{font_size:.3}" text-anchor="middle" dominant-baseline="central" paint-order="stroke" stroke-width="{:.3}" {} {}>{}</text>"#,
+            pos.x,
+            pos.y,
+            font_size * 0.3,
+            paint("fill", self.visuals.strong_text_color()),
+            paint(
+                "stroke",
+                self.visuals
+                    .panel_fill
+                    .gamma_multiply(labels.background_opacity),
+            ),
+            escape(text),
+        );
+    }
+
+    fn transform(&self, p: Vec2) -> Vec2 {
+        p * self.options.scale
+    }
+}
+
+/// Returns SVG path data for a sphene with its chord from `top` to `bottom`,
+/// going clockwise along an arc with radius `ra` and then back along an arc
+/// with radius `rb`.
+pub(super) fn lens_path(top: Vec2, bottom: Vec2, ra: f32, rb: f32) -> String {
+    format!(
+        "M {:.3} {:.3} A {ra:.3} {ra:.3} 0 0 1 {:.3} {:.3} A {rb:.3} {rb:.3} 0 0 1 {:.3} {:.3} Z",
+        top.x, top.y, bottom.x, bottom.y, top.x, top.y,
+    )
+}
+
+/// Returns SVG attributes for painting with `color`, where `attr` is `fill` or
+/// `stroke`.
+pub(super) fn paint(attr: &str, color: Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    let mut ret = format!(r##"{attr}="#{r:02x}{g:02x}{b:02x}""##);
+    if a < 255 {
+        let _ = write!(ret, r#" {attr}-opacity="{:.3}""#, a as f32 / 255.0);
+    }
+    ret
+}
+
+pub(super) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,247 @@
+//! Printable 1:1 cut template for building a physical puzzle.
+
+use std::fmt::Write;
+
+use egui::{Vec2, vec2};
+
+use super::svg::{escape, lens_path};
+use crate::util::rotate_point;
+use crate::{Grip, LabelScheme, PuzzleConfig};
+
+/// Margin around the template, in millimeters.
+const MARGIN: f32 = 15.0;
+/// Gap between parts, in millimeters.
+const GAP: f32 = 8.0;
+/// Line width for cut lines, in millimeters.
+const LINE_WIDTH: f32 = 0.2;
+/// Font size for labels, in millimeters.
+const FONT_SIZE: f32 = 3.5;
+/// Length of registration marks, in millimeters.
+const MARK_LENGTH: f32 = 5.0;
+/// Length of the scale bar, in millimeters.
+const SCALE_BAR_LENGTH: f32 = 50.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateOptions {
+    /// Edge length of one piece, in millimeters.
+    pub edge_length_mm: f32,
+    pub labels: LabelScheme,
+}
+
+impl Default for TemplateOptions {
+    fn default() -> Self {
+        Self {
+            edge_length_mm: 20.0,
+            labels: LabelScheme::default(),
+        }
+    }
+}
+
+/// Returns an SVG document with a 1:1 scale cut template for both disks and
+/// every sphene, with registration marks and a parts list. Print it at 100%
+/// scale and check the scale bar.
+pub fn export_template(config: PuzzleConfig, options: &TemplateOptions) -> String {
+    let e = options.edge_length_mm;
+    let (ra, rb) = (config.radius(Grip::A) * e, config.radius(Grip::B) * e);
+
+    let mut out = String::new();
+    let line = |out: &mut String, a: Vec2, b: Vec2| {
+        let _ = writeln!(
+            out,
+            r#"<line x1="{:.3}" y1="{:.3}" x2="{:.3}" y2="{:.3}"/>"#,
+            a.x, a.y, b.x, b.y,
+        );
+    };
+    let text = |out: &mut String, pos: Vec2, s: &str| {
+        let _ = writeln!(
+            out,
+            r#"<text x="{:.3}" y="{:.3}" stroke="none" fill="black">{}</text>"#,
+            pos.x,
+            pos.y,
+            escape(s),
+        );
+    };
+
+    // Disks, each with its sectors and a registration mark at the center.
+    let disks_top = MARGIN;
+    let disk_centers = [
+        (Grip::A, vec2(MARGIN + ra, disks_top + ra)),
+        (Grip::B, vec2(MARGIN + ra * 2.0 + GAP + rb, disks_top + rb)),
+    ];
+    for (grip, center) in disk_centers {
+        let r = config.radius(grip) * e;
+        let _ = writeln!(
+            out,
+            r#"<circle cx="{:.3}" cy="{:.3}" r="{r:.3}"/>"#,
+            center.x, center.y,
+        );
+
+        let sign = match grip {
+            Grip::A => 1.0,
+            Grip::B => -1.0,
+        };
+        let sector_radius = config.sector_radius(grip) * e;
+        let half_angle = std::f32::consts::PI / config.n(grip) as f32;
+        for i in 0..config.n(grip) {
+            let angle = config.slot_angle(grip, i) + half_angle;
+            let edge = center + vec2(sector_radius * sign, 0.0);
+            line(&mut out, center, rotate_point(edge, center, angle));
+        }
+        if sector_radius < r {
+            let _ = writeln!(
+                out,
+                r#"<circle cx="{:.3}" cy="{:.3}" r="{sector_radius:.3}" stroke-dasharray="1 1"/>"#,
+                center.x, center.y,
+            );
+        }
+
+        // Sphene outlines in their home slots, dashed, for positioning.
+        let sphene_offset = (config.sphene_center(grip) - config.center(grip)) * e;
+        for i in 0..config.n(grip) {
+            let angle = config.slot_angle(grip, i);
+            let top = rotate_point(center + sphene_offset - vec2(0.0, e / 2.0), center, angle);
+            let bottom = rotate_point(center + sphene_offset + vec2(0.0, e / 2.0), center, angle);
+            let _ = writeln!(
+                out,
+                r#"<path d="{}" stroke-dasharray="2 1"/>"#,
+                lens_path(top, bottom, ra, rb),
+            );
+            // Label each outline with the piece that belongs there.
+            if options.labels.show_sticker_labels {
+                let pos = rotate_point(center + sphene_offset, center, angle);
+                let piece = config.color_index_in_grip(grip, i);
+                let name = options.labels.sticker_name(config, piece);
+                text(&mut out, pos + vec2(0.0, FONT_SIZE / 3.0), &name);
+            }
+        }
+
+        let cross = MARK_LENGTH / 2.0;
+        line(
+            &mut out,
+            center - vec2(cross, 0.0),
+            center + vec2(cross, 0.0),
+        );
+        line(
+            &mut out,
+            center - vec2(0.0, cross),
+            center + vec2(0.0, cross),
+        );
+    }
+
+    // Sphene pieces, laid out in a grid.
+    let sphene_width = (config.radius(Grip::A) - config.apothem(Grip::A) + config.radius(Grip::B)
+        - config.apothem(Grip::B))
+        * e;
+    let pieces_top = disks_top + f32::max(ra, rb) * 2.0 + GAP;
+    let content_width = (ra + rb) * 2.0 + GAP;
+    let columns = ((content_width + GAP) / (sphene_width + GAP))
+        .floor()
+        .max(1.0) as u32;
+    let piece_count = config.piece_count();
+    for p in 0..piece_count {
+        let (col, row) = (p % columns, p / columns);
+        // The chord is offset so that the whole lens fits in its cell.
+        let chord_x = MARGIN
+            + col as f32 * (sphene_width + GAP)
+            + (config.radius(Grip::B) - config.apothem(Grip::B)) * e;
+        let chord_y = pieces_top + row as f32 * (e + GAP) + e / 2.0;
+        let top = vec2(chord_x, chord_y - e / 2.0);
+        let bottom = vec2(chord_x, chord_y + e / 2.0);
+        let _ = writeln!(out, r#"<path d="{}"/>"#, lens_path(top, bottom, ra, rb));
+        if options.labels.show_sticker_labels {
+            let name = options.labels.sticker_name(config, p);
+            text(&mut out, vec2(chord_x, chord_y + FONT_SIZE / 3.0), &name);
+        }
+    }
+    let rows = piece_count.div_ceil(columns);
+    let pieces_bottom = pieces_top + rows as f32 * (e + GAP) - GAP;
+
+    // Scale bar
+    let scale_bar_y = pieces_bottom + GAP;
+    let scale_bar_start = vec2(MARGIN, scale_bar_y);
+    let scale_bar_end = scale_bar_start + vec2(SCALE_BAR_LENGTH, 0.0);
+    line(&mut out, scale_bar_start, scale_bar_end);
+    for x in [scale_bar_start, scale_bar_end] {
+        line(&mut out, x - vec2(0.0, 1.5), x + vec2(0.0, 1.5));
+    }
+    text(
+        &mut out,
+        scale_bar_end + vec2(GAP * 2.0, FONT_SIZE / 3.0),
+        &format!("{SCALE_BAR_LENGTH} mm"),
+    );
+
+    // Parts list
+    let mut parts_y = scale_bar_y + GAP + FONT_SIZE;
+    let disk_desc = |grip: Grip| {
+        let side = match grip {
+            Grip::A => "Left",
+            Grip::B => "Right",
+        };
+        let axis = match config.axis_stationary(grip) {
+            true => "fixed",
+            false => "moving",
+        };
+        format!(
+            "1 × {side} disk ({axis}), {} sectors, radius {:.2} mm",
+            config.n(grip),
+            config.radius(grip) * e,
+        )
+    };
+    let parts = [
+        format!("Parts list for {config} with edge length {e} mm"),
+        disk_desc(Grip::A),
+        disk_desc(Grip::B),
+        format!(
+            "{piece_count} × sphene ({} shared), chord {e} mm, width {sphene_width:.2} mm",
+            config.shared,
+        ),
+        format!("Disk centers {:.2} mm apart", config.separation() * e),
+    ];
+    for part in parts {
+        let _ = writeln!(
+            out,
+            r#"<text x="{MARGIN}" y="{parts_y:.3}" stroke="none" fill="black" text-anchor="start">{}</text>"#,
+            escape(&part),
+        );
+        parts_y += FONT_SIZE * 1.5;
+    }
+
+    // Registration marks in each corner
+    let width = content_width + MARGIN * 2.0;
+    let height = parts_y + MARGIN;
+    for (x, y, dx, dy) in [
+        (MARGIN / 2.0, MARGIN / 2.0, 1.0, 1.0),
+        (width - MARGIN / 2.0, MARGIN / 2.0, -1.0, 1.0),
+        (MARGIN / 2.0, height - MARGIN / 2.0, 1.0, -1.0),
+        (width - MARGIN / 2.0, height - MARGIN / 2.0, -1.0, -1.0),
+    ] {
+        let corner = vec2(x, y);
+        line(&mut out, corner, corner + vec2(MARK_LENGTH * dx, 0.0));
+        line(&mut out, corner, corner + vec2(0.0, MARK_LENGTH * dy));
+    }
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width:.3}mm" height="{height:.3}mm" viewBox="0 0 {width:.3} {height:.3}">
+<g fill="none" stroke="black" stroke-width="{LINE_WIDTH}" font-family="sans-serif" font-size="{FONT_SIZE}" text-anchor="middle">
+{out}</g>
+</svg>
+"#
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parts_list() {
+        let config: PuzzleConfig = "M6M6x2".parse().expect("invalid puzzle");
+        let options = TemplateOptions::default();
+        let svg = export_template(config, &options);
+        assert!(svg.contains("10 × sphene (2 shared)"));
+
+        let e = options.edge_length_mm;
+        let distance = (config.center(Grip::B) - config.center(Grip::A)).length() * e;
+        assert!(svg.contains(&format!("Disk centers {distance:.2} mm apart")));
+    }
+}
@@ -0,0 +1,52 @@
+//! Fewest-moves challenge: find a short solution to a scramble, with unlimited
+//! time.
+
+use crate::{Algorithm, Metric, PuzzleConfig, PuzzleState};
+
+/// Result of a valid solution.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FmcResult {
+    pub metric: Metric,
+    /// Number of moves in the solution after simplifying it.
+    pub moves: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FmcAttempt {
+    pub config: PuzzleConfig,
+    pub scramble: PuzzleState,
+    /// Solution entered by the user, in notation.
+    pub solution: String,
+    /// Result of the last submitted solution, or an error if it was invalid.
+    pub result: Option<Result<FmcResult, String>>,
+}
+
+impl FmcAttempt {
+    pub fn new(config: PuzzleConfig, scramble: PuzzleState) -> Self {
+        Self {
+            config,
+            scramble,
+            solution: String::new(),
+            result: None,
+        }
+    }
+
+    /// Checks that the solution solves the scramble and counts its moves.
+    pub fn submit(&mut self, metric: Metric) {
+        let result = self.check(metric);
+        self.result = Some(result);
+    }
+
+    fn check(&self, metric: Metric) -> Result<FmcResult, String> {
+        let alg: Algorithm = self.solution.parse()?;
+        let mut state = self.scramble.clone();
+        alg.apply(self.config, &mut state);
+        if !state.is_solved(self.config) {
+            return Err("the solution does not solve the scramble".to_string());
+        }
+        Ok(FmcResult {
+            metric,
+            moves: alg.move_count(self.config, metric),
+        })
+    }
+}
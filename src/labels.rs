@@ -0,0 +1,101 @@
+//! Label schemes for sectors and stickers.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Grip, PuzzleConfig};
+
+/// How pieces are named.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum LabelStyle {
+    /// Letters increasing clockwise on the left disk, numbers increasing
+    /// counterclockwise on the right disk, and a dot for the center.
+    #[default]
+    Classic,
+    /// Numbers for every piece, starting from zero at the center.
+    Numbers,
+    /// Letters for every piece, starting from `A` at the center.
+    Letters,
+    /// Names chosen by the user.
+    Custom,
+}
+
+impl fmt::Display for LabelStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl LabelStyle {
+    pub const ALL: [Self; 4] = [Self::Classic, Self::Numbers, Self::Letters, Self::Custom];
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(default)]
+pub struct LabelScheme {
+    pub style: LabelStyle,
+    /// Names chosen by the user, indexed by piece. Pieces without a name fall
+    /// back to the classic style.
+    pub custom_names: Vec<String>,
+    pub show_sector_labels: bool,
+    pub show_sticker_labels: bool,
+    /// Scale factor for label text.
+    pub size: f32,
+    /// Opacity of the background behind label text.
+    pub background_opacity: f32,
+}
+
+impl Default for LabelScheme {
+    fn default() -> Self {
+        Self {
+            style: LabelStyle::Classic,
+            custom_names: vec![],
+            show_sector_labels: true,
+            show_sticker_labels: true,
+            size: 1.0,
+            background_opacity: 0.8,
+        }
+    }
+}
+
+impl LabelScheme {
+    /// Returns a scheme with all labels hidden.
+    pub fn hidden() -> Self {
+        Self {
+            show_sector_labels: false,
+            show_sticker_labels: false,
+            ..Default::default()
+        }
+    }
+
+    pub fn sector_name(&self, config: PuzzleConfig, grip: Grip, i: u32) -> String {
+        self.sticker_name(config, config.color_index_in_grip(grip, i))
+    }
+    pub fn sticker_name(&self, config: PuzzleConfig, i: u32) -> String {
+        match self.style {
+            LabelStyle::Classic => config.sticker_name(i),
+            LabelStyle::Numbers => i.to_string(),
+            LabelStyle::Letters => letters(i),
+            LabelStyle::Custom => match self.custom_names.get(i as usize) {
+                Some(name) if !name.is_empty() => name.clone(),
+                _ => config.sticker_name(i),
+            },
+        }
+    }
+}
+
+/// Returns spreadsheet-style letters for `i`: `A` through `Z`, then `AA`, `AB`,
+/// and so on.
+fn letters(mut i: u32) -> String {
+    let mut s = vec![];
+    loop {
+        s.push(b'A' + (i % 26) as u8);
+        if i < 26 {
+            break;
+        }
+        i = i / 26 - 1;
+    }
+    s.reverse();
+    String::from_utf8(s).unwrap_or_default()
+}
@@ -1,8 +1,23 @@
 mod app;
+mod bld;
+mod colors;
+mod daily;
+mod easing;
+pub mod export;
+mod fmc;
+mod labels;
 mod prefs;
 mod puzzle;
+mod round;
+mod timer;
+mod trainer;
 mod util;
 
 pub use app::App;
+pub use colors::{ColorDeficiency, ColorScheme, Gradient, Palette};
+pub use easing::Easing;
+pub use labels::{LabelScheme, LabelStyle};
 pub use prefs::Preferences;
 pub use puzzle::*;
+pub use round::{Round, RoundSolve};
+pub use timer::{Penalty, SolveResult};
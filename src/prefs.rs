@@ -1,27 +1,90 @@
 use egui::*;
 
-use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Deserializer, Serialize, de};
+
+use crate::timer::TimerStart;
+use crate::{
+    ColorDeficiency, ColorScheme, Easing, Gradient, LabelScheme, LabelStyle, Metric, PuzzleConfig,
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(default)]
 pub struct Preferences {
     pub twist_duration: f32,
-    pub show_labels: bool,
+    /// Curve followed by each twist animation.
+    pub twist_easing: Easing,
+    /// Whether to speed up twists if there are more in the queue.
+    pub dynamic_twist_speed: bool,
+    /// Higher number means faster exponential increase in twist speed.
+    pub twist_speed_up_factor: f32,
+    /// Whether to animate twists between scrambling and solving the puzzle.
+    pub animate_solves: bool,
+    /// Label settings. Older versions saved a single `show_labels` flag
+    /// instead, which is still read.
+    #[serde(alias = "show_labels", deserialize_with = "deserialize_labels")]
+    pub labels: LabelScheme,
+    /// Whether to draw a distinct shape on each piece.
+    pub show_glyphs: bool,
+    /// Whether to rotate the puzzle 90 degrees when there is more space
+    /// vertically than horizontally.
+    pub rotate_in_portrait: bool,
+    pub show_tracking_trail: bool,
+    pub show_twist_preview: bool,
+    pub show_progress: bool,
+    pub show_placement_overlay: bool,
+    pub show_distance_to_solved: bool,
     pub sector_click_mode: bool,
+    /// Whether a flicked disk keeps turning after release before snapping to
+    /// a sector.
+    pub drag_inertia: bool,
+    /// Whether to use larger hit areas and controls for touch screens.
+    pub touch_mode: bool,
+    /// Whether to time solves after scrambling.
+    pub show_timer: bool,
+    /// Whether to count down 15 seconds of inspection before each solve.
+    pub inspection: bool,
+    pub timer_start: TimerStart,
+    /// How moves are counted in fewest-moves challenges.
+    pub fmc_metric: Metric,
+    /// Colors currently in use.
+    pub colors: ColorScheme,
+    /// Color schemes saved by the user, by name.
+    pub color_schemes: BTreeMap<String, ColorScheme>,
 }
 
 impl Default for Preferences {
     fn default() -> Self {
         Self {
             twist_duration: 0.2,
-            show_labels: true,
+            twist_easing: Easing::Cosine,
+            dynamic_twist_speed: true,
+            twist_speed_up_factor: 0.2,
+            animate_solves: true,
+            labels: LabelScheme::default(),
+            show_glyphs: false,
+            rotate_in_portrait: false,
+            show_tracking_trail: true,
+            show_twist_preview: false,
+            show_progress: true,
+            show_placement_overlay: false,
+            show_distance_to_solved: false,
             sector_click_mode: false,
+            drag_inertia: true,
+            touch_mode: false,
+            show_timer: true,
+            inspection: false,
+            timer_start: TimerStart::FirstTwist,
+            fmc_metric: Metric::Disk,
+            colors: ColorScheme::default(),
+            color_schemes: BTreeMap::new(),
         }
     }
 }
 
 impl Preferences {
-    pub fn show_interaction_prefs(&mut self, ui: &mut Ui) {
+    pub fn show_animation_prefs(&mut self, ui: &mut Ui) {
         let defaults = Self::default();
 
         show_with_reset_button(
@@ -33,6 +96,60 @@ impl Preferences {
                 ui.label("Twist duration");
             },
         );
+        show_with_reset_button(
+            ui,
+            &mut self.twist_easing,
+            defaults.twist_easing,
+            |ui, current| {
+                ComboBox::from_id_salt("twist_easing")
+                    .selected_text(current.to_string())
+                    .show_ui(ui, |ui| {
+                        for easing in Easing::ALL {
+                            ui.selectable_value(current, easing, easing.to_string());
+                        }
+                    });
+                ui.label("Easing");
+            },
+        );
+        show_with_reset_button(
+            ui,
+            &mut self.dynamic_twist_speed,
+            defaults.dynamic_twist_speed,
+            |ui, current| {
+                ui.checkbox(current, "Speed up queued twists")
+                    .on_hover_text("Twist faster when several twists are waiting to be animated");
+            },
+        );
+        ui.add_enabled_ui(self.dynamic_twist_speed, |ui| {
+            show_with_reset_button(
+                ui,
+                &mut self.twist_speed_up_factor,
+                defaults.twist_speed_up_factor,
+                |ui, current| {
+                    ui.add(DragValue::new(current).range(0.0..=1.0).speed(0.01));
+                    ui.label("Speed-up factor");
+                },
+            );
+        });
+
+        show_with_reset_button(
+            ui,
+            &mut self.animate_solves,
+            defaults.animate_solves,
+            |ui, current| {
+                ui.checkbox(current, "Animate during solves")
+                    .on_hover_text("When disabled, twists apply instantly after scrambling");
+            },
+        );
+
+        CollapsingHeader::new("Preview")
+            .id_salt("twist_preview")
+            .show(ui, |ui| show_twist_preview(ui, self));
+    }
+
+    pub fn show_interaction_prefs(&mut self, ui: &mut Ui) {
+        let defaults = Self::default();
+
         show_with_reset_button(
             ui,
             &mut self.sector_click_mode,
@@ -51,13 +168,342 @@ impl Preferences {
                 );
             },
         );
+        show_with_reset_button(
+            ui,
+            &mut self.drag_inertia,
+            defaults.drag_inertia,
+            |ui, current| {
+                ui.checkbox(current, "Drag inertia")
+                    .on_hover_text("Let a flicked disk coast before snapping to a sector");
+            },
+        );
+        show_with_reset_button(
+            ui,
+            &mut self.touch_mode,
+            defaults.touch_mode,
+            |ui, current| {
+                ui.checkbox(current, "Touch mode")
+                    .on_hover_text("Use larger hit areas and controls for touch screens");
+            },
+        );
+    }
+
+    pub fn show_timer_prefs(&mut self, ui: &mut Ui) {
+        let defaults = Self::default();
+
+        show_with_reset_button(
+            ui,
+            &mut self.show_timer,
+            defaults.show_timer,
+            |ui, current| {
+                ui.checkbox(current, "Time solves");
+            },
+        );
+        ui.add_enabled_ui(self.show_timer, |ui| {
+            show_with_reset_button(
+                ui,
+                &mut self.inspection,
+                defaults.inspection,
+                |ui, current| {
+                    ui.checkbox(current, "Inspection").on_hover_text(
+                        "Count down 15 seconds before each solve. \
+                         Starting after 15 seconds adds 2 seconds, \
+                         and starting after 17 seconds is a DNF.",
+                    );
+                },
+            );
+            show_with_reset_button(
+                ui,
+                &mut self.timer_start,
+                defaults.timer_start,
+                |ui, current| {
+                    for start in [TimerStart::FirstTwist, TimerStart::Stackmat] {
+                        ui.selectable_value(current, start, start.to_string());
+                    }
+                    ui.label("Start").on_hover_text(
+                        "Hold Space: hold Space for half a second and release it to start, \
+                         and press Space to stop",
+                    );
+                },
+            );
+        });
+    }
+
+    pub fn show_fmc_prefs(&mut self, ui: &mut Ui) {
+        let defaults = Self::default();
+
+        show_with_reset_button(
+            ui,
+            &mut self.fmc_metric,
+            defaults.fmc_metric,
+            |ui, current| {
+                for metric in [Metric::Disk, Metric::Sector] {
+                    ui.selectable_value(current, metric, metric.to_string());
+                }
+                ui.label("Metric").on_hover_text(
+                    "Disk twists: a twist of one disk by any amount is one move\n\
+                     Sectors: each sector a disk is twisted by is one move",
+                );
+            },
+        );
     }
 
     pub fn show_visuals_prefs(&mut self, ui: &mut Ui) {
-        ui.checkbox(&mut self.show_labels, "Show labels");
+        ui.checkbox(&mut self.show_glyphs, "Show glyphs")
+            .on_hover_text("Draw a distinct shape on each piece, to tell them apart without color");
+        ui.checkbox(
+            &mut self.rotate_in_portrait,
+            "Rotate puzzle in portrait layout",
+        );
+        ui.checkbox(&mut self.show_tracking_trail, "Show tracked piece trail");
+        ui.checkbox(&mut self.show_twist_preview, "Preview twist on hover")
+            .on_hover_text("Show where each piece would travel when clicking");
+        ui.checkbox(&mut self.show_progress, "Show progress");
+        ui.add_enabled_ui(self.show_progress, |ui| {
+            ui.indent("progress", |ui| {
+                ui.checkbox(&mut self.show_placement_overlay, "Mark pieces in place");
+                ui.checkbox(&mut self.show_distance_to_solved, "Show distance to solved")
+                    .on_hover_text("Only available for small puzzles");
+            });
+        });
 
         egui::widgets::global_theme_preference_buttons(ui);
     }
+
+    pub fn show_label_prefs(&mut self, ui: &mut Ui, config: PuzzleConfig) {
+        let defaults = LabelScheme::default();
+        let labels = &mut self.labels;
+
+        show_with_reset_button(ui, &mut labels.style, defaults.style, |ui, current| {
+            ComboBox::from_id_salt("label_style")
+                .selected_text(current.to_string())
+                .show_ui(ui, |ui| {
+                    for style in LabelStyle::ALL {
+                        ui.selectable_value(current, style, style.to_string());
+                    }
+                });
+            ui.label("Style");
+        });
+        if labels.style == LabelStyle::Custom {
+            let piece_count = config.piece_count() as usize;
+            if labels.custom_names.len() < piece_count {
+                labels.custom_names.resize(piece_count, String::new());
+            }
+            Grid::new("custom_label_names").show(ui, |ui| {
+                for i in 0..config.piece_count() {
+                    ui.label(config.sticker_name(i));
+                    ui.add(
+                        TextEdit::singleline(&mut labels.custom_names[i as usize])
+                            .hint_text(config.sticker_name(i))
+                            .desired_width(80.0),
+                    );
+                    ui.end_row();
+                }
+            });
+        }
+        ui.checkbox(&mut labels.show_sector_labels, "Show sector labels");
+        ui.checkbox(&mut labels.show_sticker_labels, "Show sticker labels");
+        show_with_reset_button(ui, &mut labels.size, defaults.size, |ui, current| {
+            ui.add(DragValue::new(current).range(0.5..=3.0).speed(0.01));
+            ui.label("Size");
+        });
+        show_with_reset_button(
+            ui,
+            &mut labels.background_opacity,
+            defaults.background_opacity,
+            |ui, current| {
+                ui.add(Slider::new(current, 0.0..=1.0));
+                ui.label("Background opacity");
+            },
+        );
+    }
+
+    pub fn show_color_prefs(&mut self, ui: &mut Ui, config: PuzzleConfig) {
+        let dark_mode = ui.visuals().dark_mode;
+
+        ui.label(match dark_mode {
+            true => "Editing colors for dark mode",
+            false => "Editing colors for light mode",
+        });
+        let palette = self.colors.palette_mut(dark_mode);
+        show_with_reset_button(
+            ui,
+            &mut palette.gradient,
+            Gradient::default(),
+            |ui, current| {
+                ComboBox::from_id_salt("gradient")
+                    .selected_text(current.to_string())
+                    .show_ui(ui, |ui| {
+                        for gradient in Gradient::ALL {
+                            ui.selectable_value(current, gradient, gradient.to_string());
+                        }
+                    });
+                ui.label("Gradient");
+            },
+        );
+
+        ui.horizontal_wrapped(|ui| {
+            for i in 0..config.piece_count() {
+                let mut color = self.colors.sticker_color(config, i, dark_mode);
+                let palette = self.colors.palette_mut(dark_mode);
+                let mut piece_color = palette.piece_color(i);
+                show_with_reset_button(ui, &mut piece_color, None, |ui, current| {
+                    if ui.color_edit_button_srgba(&mut color).changed() {
+                        *current = Some(color);
+                    }
+                    ui.label(self.labels.sticker_name(config, i));
+                });
+                palette.set_piece_color(i, piece_color);
+            }
+        });
+        ui.scope(|ui| {
+            let palette = self.colors.palette_mut(dark_mode);
+            if palette.piece_colors.is_empty() {
+                ui.disable();
+            }
+            if ui.button("Reset piece colors").clicked() {
+                palette.piece_colors.clear();
+            }
+        });
+
+        CollapsingHeader::new("Color vision simulation").show(ui, |ui| {
+            let colors: Vec<Color32> = (0..config.piece_count())
+                .map(|i| self.colors.sticker_color(config, i, dark_mode))
+                .collect();
+            Grid::new("color_vision_simulation").show(ui, |ui| {
+                ui.label("Normal");
+                show_swatches(ui, colors.iter().copied());
+                ui.end_row();
+                for deficiency in ColorDeficiency::ALL {
+                    ui.label(deficiency.to_string());
+                    show_swatches(ui, colors.iter().map(|&c| deficiency.simulate(c)));
+                    ui.end_row();
+                }
+            });
+        });
+
+        ui.separator();
+
+        let name_id = ui.id().with("color_scheme_name");
+        let mut name = ui.data_mut(|data| data.get_temp::<String>(name_id).unwrap_or_default());
+        ui.horizontal(|ui| {
+            ComboBox::from_id_salt("color_schemes")
+                .selected_text("Load scheme")
+                .show_ui(ui, |ui| {
+                    for (scheme_name, scheme) in &self.color_schemes {
+                        let is_current = *scheme == self.colors;
+                        if ui.selectable_label(is_current, scheme_name).clicked() {
+                            self.colors = scheme.clone();
+                            name = scheme_name.clone();
+                        }
+                    }
+                    if self.color_schemes.is_empty() {
+                        ui.label("No saved schemes");
+                    }
+                });
+            if ui.button("Load default").clicked() {
+                self.colors = ColorScheme::default();
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                TextEdit::singleline(&mut name)
+                    .hint_text("Name")
+                    .desired_width(120.0),
+            );
+            let name = name.trim();
+            ui.add_enabled_ui(!name.is_empty(), |ui| {
+                if ui.button("Save").clicked() {
+                    self.color_schemes
+                        .insert(name.to_string(), self.colors.clone());
+                }
+            });
+            ui.add_enabled_ui(self.color_schemes.contains_key(name), |ui| {
+                if ui.button("Delete").clicked() {
+                    self.color_schemes.remove(name);
+                }
+            });
+        });
+        ui.data_mut(|data| data.insert_temp(name_id, name));
+    }
+}
+
+/// Shows a disk twisting repeatedly by one sector, next to a plot of the
+/// easing curve.
+fn show_twist_preview(ui: &mut Ui, prefs: &Preferences) {
+    const SECTORS: u32 = 6;
+    const PAUSE: f32 = 0.5;
+
+    let time = ui.input(|input| input.time) as f32;
+    let duration = prefs.twist_duration.max(0.001);
+    let cycle = duration + PAUSE;
+    let progress = ((time % cycle) / duration).min(1.0);
+    let twists_done = (time / cycle).floor() % SECTORS as f32;
+    let sector_angle = std::f32::consts::TAU / SECTORS as f32;
+    let angle = (twists_done + prefs.twist_easing.apply(progress)) * sector_angle;
+
+    let visuals = ui.visuals().clone();
+    let stroke = Stroke::new(1.0, visuals.strong_text_color());
+    let size = 64.0;
+    ui.horizontal(|ui| {
+        // Disk
+        let (rect, _) = ui.allocate_exact_size(vec2(size, size), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let center = rect.center();
+        let r = size / 2.0 - 2.0;
+        let point_at = |a: f32| center + r * vec2(a.sin(), -a.cos());
+        painter.circle_filled(center, r, visuals.extreme_bg_color);
+        painter.add(Shape::convex_polygon(
+            vec![center, point_at(angle), point_at(angle + sector_angle)],
+            visuals.selection.bg_fill,
+            Stroke::NONE,
+        ));
+        for i in 0..SECTORS {
+            painter.line_segment([center, point_at(angle + i as f32 * sector_angle)], stroke);
+        }
+        painter.circle_stroke(center, r, stroke);
+
+        // Easing curve
+        let (rect, _) = ui.allocate_exact_size(vec2(size * 1.5, size), Sense::hover());
+        let painter = ui.painter_at(rect);
+        let plot_rect = rect.shrink2(vec2(2.0, size * 0.2));
+        let to_screen = |t: f32, y: f32| {
+            pos2(
+                lerp(plot_rect.x_range(), t),
+                lerp(plot_rect.bottom()..=plot_rect.top(), y),
+            )
+        };
+        painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+        painter.line_segment(
+            [to_screen(0.0, 1.0), to_screen(1.0, 1.0)],
+            visuals.widgets.noninteractive.bg_stroke,
+        );
+        let points = (0..=48)
+            .map(|i| {
+                let t = i as f32 / 48.0;
+                to_screen(t, prefs.twist_easing.apply(t))
+            })
+            .collect();
+        painter.add(Shape::line(points, stroke));
+        painter.circle_filled(
+            to_screen(progress, prefs.twist_easing.apply(progress)),
+            3.0,
+            visuals.selection.stroke.color,
+        );
+    });
+
+    ui.ctx().request_repaint();
+}
+
+/// Shows a row of small squares filled with `colors`.
+fn show_swatches(ui: &mut Ui, colors: impl IntoIterator<Item = Color32>) {
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 2.0;
+        for color in colors {
+            let (rect, _) = ui.allocate_exact_size(vec2(16.0, 16.0), Sense::hover());
+            ui.painter().rect_filled(rect, 2.0, color);
+        }
+    });
 }
 
 fn show_with_reset_button<T: PartialEq>(
@@ -79,3 +525,50 @@ fn show_with_reset_button<T: PartialEq>(
         show_current(ui, current);
     });
 }
+
+/// Reads label settings, or the `show_labels` flag that they replaced.
+fn deserialize_labels<'de, D: Deserializer<'de>>(deserializer: D) -> Result<LabelScheme, D::Error> {
+    struct LabelsVisitor;
+    impl<'de> de::Visitor<'de> for LabelsVisitor {
+        type Value = LabelScheme;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("label settings or a boolean")
+        }
+        fn visit_bool<E: de::Error>(self, show_labels: bool) -> Result<LabelScheme, E> {
+            Ok(match show_labels {
+                true => LabelScheme::default(),
+                false => LabelScheme::hidden(),
+            })
+        }
+        fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<LabelScheme, A::Error> {
+            LabelScheme::deserialize(de::value::MapAccessDeserializer::new(map))
+        }
+    }
+    deserializer.deserialize_any(LabelsVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_old_show_labels() {
+        let prefs: Preferences = ron::from_str("(show_labels: false)").expect("invalid prefs");
+        assert_eq!(prefs.labels, LabelScheme::hidden());
+        let prefs: Preferences = ron::from_str("(show_labels: true)").expect("invalid prefs");
+        assert_eq!(prefs.labels, LabelScheme::default());
+
+        let labels = LabelScheme {
+            style: LabelStyle::Letters,
+            ..LabelScheme::hidden()
+        };
+        let saved = ron::to_string(&Preferences {
+            labels: labels.clone(),
+            ..Default::default()
+        })
+        .expect("failed to save prefs");
+        let prefs: Preferences = ron::from_str(&saved).expect("invalid prefs");
+        assert_eq!(prefs.labels, labels);
+    }
+}
@@ -0,0 +1,271 @@
+//! Move notation.
+//!
+//! `L` and `R` twist the left and right disks clockwise by one sector. A
+//! number after the letter twists by that many sectors, and a `'` after that
+//! twists counterclockwise instead. For example, `L2'` twists the left disk
+//! counterclockwise by two sectors.
+
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{Grip, PuzzleConfig, PuzzleState, TwistDir};
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Twist {
+    pub grip: Grip,
+    pub dir: TwistDir,
+    pub amt: u32,
+}
+
+impl Twist {
+    pub fn apply(self, config: PuzzleConfig, state: &mut PuzzleState) {
+        match self.dir {
+            TwistDir::Cw => state.twist_cw(config, self.grip, self.amt),
+            TwistDir::Ccw => state.twist_ccw(config, self.grip, self.amt),
+        }
+    }
+
+    #[must_use]
+    pub fn inverse(self) -> Self {
+        Self {
+            dir: self.dir.rev(),
+            ..self
+        }
+    }
+}
+
+impl fmt::Display for Twist {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.grip {
+            Grip::A => write!(f, "L")?,
+            Grip::B => write!(f, "R")?,
+        }
+        if self.amt != 1 {
+            write!(f, "{}", self.amt)?;
+        }
+        if self.dir == TwistDir::Ccw {
+            write!(f, "'")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Twist {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Algorithm::from_str(s)?.0.as_slice() {
+            [twist] => Ok(*twist),
+            _ => Err(format!("expected a single twist, got {s:?}")),
+        }
+    }
+}
+
+/// Way of counting moves.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// A twist of one disk by any amount counts as one move.
+    #[default]
+    Disk,
+    /// Each sector that a disk is twisted by counts as one move.
+    Sector,
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::Disk => write!(f, "Disk twists"),
+            Metric::Sector => write!(f, "Sectors"),
+        }
+    }
+}
+
+/// Sequence of twists.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub struct Algorithm(pub Vec<Twist>);
+
+impl Algorithm {
+    pub fn apply(&self, config: PuzzleConfig, state: &mut PuzzleState) {
+        for twist in &self.0 {
+            twist.apply(config, state);
+        }
+    }
+
+    #[must_use]
+    pub fn inverse(&self) -> Self {
+        Self(self.0.iter().rev().map(|t| t.inverse()).collect())
+    }
+
+    /// Returns the algorithm with consecutive twists of the same disk
+    /// combined and twists that cancel out removed, so that its length is
+    /// the number of moves counted by [`DistanceTable`].
+    ///
+    /// [`DistanceTable`]: super::DistanceTable
+    #[must_use]
+    pub fn simplified(&self, config: PuzzleConfig) -> Self {
+        // Clockwise amount of a twist, reduced so that large amounts can't
+        // overflow.
+        let cw_amt = |t: Twist| {
+            let n = config.n(t.grip);
+            match t.dir {
+                TwistDir::Cw => t.amt % n,
+                TwistDir::Ccw => (n - t.amt % n) % n,
+            }
+        };
+        let mut twists: Vec<Twist> = vec![];
+        for &twist in &self.0 {
+            let n = config.n(twist.grip);
+            let mut amt = cw_amt(twist);
+            if let Some(&last) = twists.last() {
+                if last.grip == twist.grip {
+                    amt = (amt + cw_amt(last)) % n;
+                    twists.pop();
+                }
+            }
+            if amt != 0 {
+                let (dir, amt) = match amt <= n / 2 {
+                    true => (TwistDir::Cw, amt),
+                    false => (TwistDir::Ccw, n - amt),
+                };
+                twists.push(Twist {
+                    grip: twist.grip,
+                    dir,
+                    amt,
+                });
+            }
+        }
+        Self(twists)
+    }
+
+    /// Returns the number of moves in the algorithm after simplifying it,
+    /// counted using `metric`.
+    pub fn move_count(&self, config: PuzzleConfig, metric: Metric) -> usize {
+        let simplified = self.simplified(config);
+        match metric {
+            Metric::Disk => simplified.len(),
+            Metric::Sector => simplified.0.iter().map(|t| t.amt as usize).sum(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, twist) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{twist}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    /// Parses twists separated by optional whitespace, such as `L R2' L'`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut twists = vec![];
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            let grip = match c {
+                c if c.is_whitespace() => continue,
+                'L' | 'l' => Grip::A,
+                'R' | 'r' => Grip::B,
+                _ => return Err(format!("unexpected {c:?} in {s:?}; expected L or R")),
+            };
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                digits.push(d);
+            }
+            let amt = match digits.as_str() {
+                "" => 1,
+                _ => digits
+                    .parse()
+                    .ok()
+                    .filter(|&amt| amt > 0)
+                    .ok_or_else(|| format!("invalid amount {digits:?} in {s:?}"))?,
+            };
+            let dir = match chars.next_if(|&c| c == '\'') {
+                Some(_) => TwistDir::Ccw,
+                None => TwistDir::Cw,
+            };
+            twists.push(Twist { grip, dir, amt });
+        }
+        Ok(Self(twists))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn simplify(alg: &str, puzzle: &str) -> String {
+        let alg: Algorithm = alg.parse().expect("invalid algorithm");
+        let config = puzzle.parse().expect("invalid puzzle");
+        alg.simplified(config).to_string()
+    }
+
+    #[test]
+    fn test_parse_algorithm() {
+        let alg: Algorithm = "L R2' l r'".parse().expect("invalid algorithm");
+        let twist = |grip, dir, amt| Twist { grip, dir, amt };
+        assert_eq!(
+            alg.0,
+            [
+                twist(Grip::A, TwistDir::Cw, 1),
+                twist(Grip::B, TwistDir::Ccw, 2),
+                twist(Grip::A, TwistDir::Cw, 1),
+                twist(Grip::B, TwistDir::Ccw, 1),
+            ],
+        );
+        assert_eq!(alg.to_string(), "L R2' L R'");
+        assert_eq!(
+            "LR2'L".parse::<Algorithm>().map(|a| a.to_string()),
+            Ok("L R2' L".into())
+        );
+        assert_eq!("".parse(), Ok(Algorithm::default()));
+
+        for s in ["L R2' L12 R'", "R", "L4294967295'"] {
+            let alg: Algorithm = s.parse().expect("invalid algorithm");
+            assert_eq!(alg.to_string(), s);
+            assert_eq!(alg.to_string().parse(), Ok(alg));
+        }
+        for s in ["X", "L0", "L'2", "L''", "L4294967296", "'"] {
+            assert!(s.parse::<Algorithm>().is_err(), "{s:?} should be rejected");
+        }
+        assert!("L R".parse::<Twist>().is_err());
+    }
+
+    #[test]
+    fn test_simplified() {
+        assert_eq!(simplify("L R2 R' L3 L2", "M5F2"), "L R");
+        assert_eq!(simplify("L L'", "M5F2"), "");
+        assert_eq!(simplify("L R R' L'", "M5F2"), "");
+        assert_eq!(simplify("L3", "M5F2"), "L2'");
+        assert_eq!(simplify("L5 R4'", "M5M4"), "");
+        assert_eq!(simplify("L2 L2", "M4M3"), "");
+        assert_eq!(simplify("L3'", "M6F2"), "L3");
+    }
+
+    #[test]
+    fn test_simplified_large_amounts() {
+        let config = "M5F2".parse().expect("invalid puzzle");
+        let alg: Algorithm = "L2147483647 L2147483647 L4294967295'"
+            .parse()
+            .expect("invalid algorithm");
+        // 2147483647 = 2 (mod 5) and 4294967295 = 0 (mod 5)
+        assert_eq!(alg.simplified(config).to_string(), "L'");
+        assert_eq!(alg.move_count(config, Metric::Disk), 1);
+        assert_eq!(alg.move_count(config, Metric::Sector), 1);
+    }
+}
@@ -1,20 +1,29 @@
 use std::f32::consts::{PI, TAU};
+use std::fmt;
+use std::str::FromStr;
 
 use egui::*;
 use serde::{Deserialize, Serialize};
 
 use super::Grip;
 use super::Grip::{A, B};
+use crate::util::rotate_point;
 
 const CONSERVATIVENESS: u32 = 1;
 const POLYGON_RESOLUTION: u32 = 200;
 
 #[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq)]
+#[serde(default)]
 pub struct PuzzleConfig {
     pub a: u32,
     pub b: u32,
     pub a_axis_stationary: bool,
     pub b_axis_stationary: bool,
+    /// Number of consecutive slots shared between the two disks.
+    pub shared: u32,
+    /// Whether the sectors of a moving axis must be restored for the puzzle
+    /// to count as solved.
+    pub supercube: bool,
 }
 
 impl Default for PuzzleConfig {
@@ -24,17 +33,101 @@ impl Default for PuzzleConfig {
             b: 2,
             a_axis_stationary: false,
             b_axis_stationary: true,
+            shared: 1,
+            supercube: true,
         }
     }
 }
 
+/// Formats the configuration in the notation used to name puzzles, such as
+/// `M5F2` for a moving 5-sector left disk and a fixed 2-sector right disk.
+impl fmt::Display for PuzzleConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let axis = |stationary| if stationary { 'F' } else { 'M' };
+        write!(f, "{}{}", axis(self.a_axis_stationary), self.a)?;
+        write!(f, "{}{}", axis(self.b_axis_stationary), self.b)?;
+        if self.shared != 1 {
+            write!(f, "x{}", self.shared)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for PuzzleConfig {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("invalid puzzle {s:?}; expected something like M5F2");
+
+        let (s, shared) = match s.split_once(['x', 'X']) {
+            Some((s, shared)) => (s, shared.parse().map_err(|_| err())?),
+            None => (s, 1),
+        };
+        let parse_axis = |s: &str| -> Result<(bool, u32), String> {
+            let stationary = match s.chars().next() {
+                Some('F' | 'f') => true,
+                Some('M' | 'm') => false,
+                _ => return Err(err()),
+            };
+            let n = s[1..].parse().map_err(|_| err())?;
+            Ok((stationary, n))
+        };
+        let split = s
+            .get(1..)
+            .and_then(|rest| rest.find(|c: char| !c.is_ascii_digit()))
+            .ok_or_else(err)?
+            + 1;
+        let (a_axis_stationary, a) = parse_axis(&s[..split])?;
+        let (b_axis_stationary, b) = parse_axis(&s[split..])?;
+
+        let config = Self {
+            a,
+            b,
+            a_axis_stationary,
+            b_axis_stationary,
+            shared,
+            ..Default::default()
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
 impl PuzzleConfig {
+    /// Returns an error if the configuration can't be drawn or twisted, such
+    /// as one read from a file.
+    pub fn validate(self) -> Result<(), String> {
+        if !(2..=16).contains(&self.a) || !(2..=16).contains(&self.b) {
+            return Err("disks must have between 2 and 16 sectors".to_string());
+        }
+        if !(1..=self.max_shared()).contains(&self.shared) {
+            return Err(match self.max_shared() {
+                1 => "disks with different numbers of sectors must share 1 piece".to_string(),
+                max => format!("there must be between 1 and {max} shared pieces"),
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the largest number of shared pieces for which the shared slots
+    /// of both disks line up. The slot centers of each disk lie on a circle,
+    /// and two circles meet in at most two points, which are only a slot
+    /// apart on both disks if the disks have the same number of sectors.
+    pub fn max_shared(self) -> u32 {
+        let max = if self.a == self.b { 2 } else { 1 };
+        u32::min(max, u32::min(self.a, self.b).saturating_sub(1)).max(1)
+    }
+
     pub fn n(self, grip: Grip) -> u32 {
         match grip {
             Grip::A => self.a,
             Grip::B => self.b,
         }
     }
+    /// Returns the total number of distinct pieces.
+    pub fn piece_count(self) -> u32 {
+        self.a + self.b - self.shared
+    }
     pub fn radius(self, grip: Grip) -> f32 {
         polygon_circumradius(self.n(grip) + CONSERVATIVENESS)
     }
@@ -42,14 +135,42 @@ impl PuzzleConfig {
         let r = self.radius(grip);
         r * r
     }
+    pub fn apothem(self, grip: Grip) -> f32 {
+        polygon_apothem(self.n(grip) + CONSERVATIVENESS)
+    }
     pub fn center(self, grip: Grip) -> Vec2 {
         match grip {
             A => vec2(self.radius(A), self.height() * 0.5),
             B => vec2(self.width() - self.radius(B), self.height() * 0.5),
         }
     }
+    /// Returns the distance between the centers of the two disks.
+    pub fn separation(self) -> f32 {
+        self.apothem(A) * self.shared_half_angle(A).cos()
+            + self.apothem(B) * self.shared_half_angle(B).cos()
+    }
+    /// Returns the angle between the axis and the outermost shared slot.
+    fn shared_half_angle(self, grip: Grip) -> f32 {
+        (self.shared - 1) as f32 * 0.5 * TAU / self.n(grip) as f32
+    }
+    /// Returns the angle of slot `i` of a grip, measured clockwise from the
+    /// axis, such that the shared slots are centered on the axis.
+    pub fn slot_angle(self, grip: Grip, i: u32) -> f32 {
+        i as f32 * TAU / self.n(grip) as f32 - self.shared_half_angle(grip)
+    }
+
+    /// Returns the slot on the other disk that coincides with slot `i`, if it
+    /// is shared. The mapping is the same in both directions.
+    pub fn shared_slot(self, i: u32) -> Option<u32> {
+        (i < self.shared).then(|| self.shared - 1 - i)
+    }
     pub fn is_hovered(self, grip: Grip, cursor: Vec2) -> bool {
-        (cursor - self.center(grip)).length_sq() < self.radius(grip) * self.radius(grip)
+        self.is_hovered_with_margin(grip, cursor, 0.0)
+    }
+    /// Returns whether `cursor` is within `margin` of the disk of `grip`.
+    pub fn is_hovered_with_margin(self, grip: Grip, cursor: Vec2, margin: f32) -> bool {
+        let r = self.radius(grip) + margin;
+        (cursor - self.center(grip)).length_sq() < r * r
             && match grip {
                 A => cursor.x < self.midpoint_x(),
                 B => cursor.x >= self.midpoint_x(),
@@ -67,13 +188,10 @@ impl PuzzleConfig {
         f32::max(self.radius(A), self.radius(B)) * 2.0
     }
     pub fn width(self) -> f32 {
-        self.radius(A)
-            + self.radius(B)
-            + polygon_apothem(self.a + CONSERVATIVENESS)
-            + polygon_apothem(self.b + CONSERVATIVENESS)
+        self.radius(A) + self.radius(B) + self.separation()
     }
     pub fn midpoint_x(self) -> f32 {
-        self.radius(A) + polygon_apothem(self.a + CONSERVATIVENESS)
+        self.radius(A) + self.apothem(A) * self.shared_half_angle(A).cos()
     }
     pub fn midpoint(self) -> Vec2 {
         vec2(self.midpoint_x(), self.height() * 0.5)
@@ -83,102 +201,106 @@ impl PuzzleConfig {
     }
 
     pub fn hovered_grip(self, cursor: Vec2) -> Option<Grip> {
+        self.hovered_grip_with_margin(cursor, 0.0)
+    }
+    /// Returns the grip under `cursor`, counting `margin` around each disk as
+    /// part of it.
+    pub fn hovered_grip_with_margin(self, cursor: Vec2, margin: f32) -> Option<Grip> {
         Option::or(
-            self.is_hovered(A, cursor).then_some(Grip::A),
-            self.is_hovered(B, cursor).then_some(Grip::B),
+            self.is_hovered_with_margin(A, cursor, margin)
+                .then_some(Grip::A),
+            self.is_hovered_with_margin(B, cursor, margin)
+                .then_some(Grip::B),
         )
     }
 
-    pub fn sphene_points(self) -> Vec<Vec2> {
+    /// Returns the center of the sphene in slot 0 of `grip`, before rotating
+    /// it by [`Self::slot_angle()`].
+    pub fn sphene_center(self, grip: Grip) -> Vec2 {
+        match grip {
+            A => self.center(A) + vec2(self.apothem(A), 0.0),
+            B => self.center(B) - vec2(self.apothem(B), 0.0),
+        }
+    }
+
+    /// Returns the center of the sphene in slot `i` of `grip`.
+    pub fn slot_center(self, grip: Grip, i: u32) -> Vec2 {
+        rotate_point(
+            self.sphene_center(grip),
+            self.center(grip),
+            self.slot_angle(grip, i),
+        )
+    }
+    /// Returns the slot of `grip` whose sphene contains `cursor`, if any.
+    pub fn hovered_slot(self, grip: Grip, cursor: Vec2) -> Option<u32> {
+        (0..self.n(grip)).find(|&i| {
+            let p = rotate_point(cursor, self.center(grip), -self.slot_angle(grip, i))
+                - self.sphene_center(grip);
+            (p + vec2(self.apothem(A), 0.0)).length_sq() < self.radius_sq(A)
+                && (p - vec2(self.apothem(B), 0.0)).length_sq() < self.radius_sq(B)
+        })
+    }
+
+    pub fn sphene_points(self, grip: Grip) -> Vec<Vec2> {
         let mut points = vec![];
 
+        let center = self.sphene_center(grip);
+
         let resolution = POLYGON_RESOLUTION / self.a;
         for i in 0..resolution {
             let y = i as f32 / resolution as f32 - 0.5;
-            let x = self.center(A).x + (self.radius_sq(A) - y * y).sqrt();
-            points.push(vec2(x, y + self.height() * 0.5));
+            let x = (self.radius_sq(A) - y * y).sqrt() - self.apothem(A);
+            points.push(center + vec2(x, y));
         }
 
         let resolution = POLYGON_RESOLUTION / self.b;
         for i in 0..resolution {
             let y = -(i as f32 / resolution as f32 - 0.5);
-            let x = self.center(B).x - (self.radius_sq(B) - y * y).sqrt();
-            points.push(vec2(x, y + self.height() * 0.5));
+            let x = self.apothem(B) - (self.radius_sq(B) - y * y).sqrt();
+            points.push(center + vec2(x, y));
         }
 
         points
     }
 
+    /// Returns the radius of the sectors of `grip`.
+    pub fn sector_radius(self, grip: Grip) -> f32 {
+        crate::util::lerp(
+            self.radius(grip),
+            self.separation() - self.radius(grip.other()),
+            if self.axis_stationary(grip) { 0.5 } else { 0.0 },
+        )
+    }
     pub fn sector_points(self, grip: Grip) -> impl Iterator<Item = Vec2> {
         let sign = match grip {
             A => 1.0,
             B => -1.0,
         };
-
-        let radius = crate::util::lerp(
-            self.radius(grip),
-            polygon_apothem(self.a + CONSERVATIVENESS) + polygon_apothem(self.b + CONSERVATIVENESS)
-                - self.radius(grip.other()),
-            if self.axis_stationary(grip) { 0.5 } else { 0.0 },
-        );
-
+        let radius = self.sector_radius(grip);
         sector_points(TAU / self.n(grip) as f32).map(move |p| self.center(grip) + p * radius * sign)
     }
-
-    fn shared_color(self, brightness: f32, dark_mode: bool) -> Color32 {
-        if self.b == 2 {
-            sample_rainbow(0, 1, brightness * 0.5)
-        } else {
-            sample_rainbow(0, 1, brightness * if dark_mode { 0.45 } else { 0.65 })
-        }
-    }
-    fn a_color(self, i: u32, brightness: f32, dark_mode: bool) -> Color32 {
-        if i == 0 {
-            self.shared_color(brightness, dark_mode)
-        } else {
-            sample_rainbow(self.a - i, self.a, brightness * 0.5)
-        }
-    }
-    fn b_color(self, i: u32, brightness: f32, dark_mode: bool) -> Color32 {
-        if i == 0 {
-            self.shared_color(brightness, dark_mode)
-        } else if self.b == 2 {
-            Color32::DARK_GRAY
-        } else {
-            sample_rainbow(i, self.b, brightness * if dark_mode { 0.25 } else { 0.75 })
-        }
-    }
-
-    pub fn color(self, i: u32, brightness: f32, dark_mode: bool) -> Color32 {
-        if i < self.a {
-            self.a_color(i, brightness, dark_mode)
-        } else {
-            self.b_color(i - self.a + 1, brightness, dark_mode)
-        }
-    }
-    pub fn sticker_color(self, i: u32, dark_mode: bool) -> Color32 {
-        self.color(i, if dark_mode { 1.0 } else { 0.85 }, dark_mode)
-    }
-    pub fn sector_color(self, i: u32, dark_mode: bool) -> Color32 {
-        self.color(i, 0.9, dark_mode)
+    /// Returns the position of the label for sector 0 of `grip`, before
+    /// rotating it by [`Self::slot_angle()`].
+    pub fn sector_label_pos(self, grip: Grip) -> Vec2 {
+        crate::util::lerp(
+            self.center(grip),
+            self.sphene_center(grip),
+            (1.0 - (4.0 / self.n(grip) as f32)).at_least(1.0 / 3.0),
+        )
     }
 
     pub fn color_index_in_grip(self, grip: Grip, i: u32) -> u32 {
-        if grip == B && i > 0 {
-            i + self.a - 1
-        } else {
-            i
+        match grip {
+            A => i,
+            B => match self.shared_slot(i) {
+                Some(j) => j,
+                None => i + self.a - self.shared,
+            },
         }
     }
 
     pub fn sector_name(self, grip: Grip, i: u32) -> String {
-        if i == 0 {
-            return "•".to_string();
-        }
-        match grip {
-            A => ((b'A' + i as u8 - 1) as char).to_string(),
-            B => (self.b - i).to_string(),
-        }
+        self.sticker_name(self.color_index_in_grip(grip, i))
     }
     pub fn sticker_name(self, i: u32) -> String {
         if i == 0 {
@@ -187,7 +309,7 @@ impl PuzzleConfig {
         if i < self.n(A) {
             ((b'A' + i as u8 - 1) as char).to_string()
         } else {
-            (self.a + self.b - 1 - i).to_string()
+            (self.a + self.b - self.shared - i).to_string()
         }
     }
 }
@@ -213,14 +335,59 @@ fn sector_points(angle: f32) -> impl Iterator<Item = Vec2> {
         .chain([Vec2::ZERO])
 }
 
-/// Samples a rainbow with `n` colors at index `i`. `lightness` ranges from 0 to
-/// 1, with 0.5 being default.
-fn sample_rainbow(i: u32, n: u32, lightness: f32) -> Color32 {
-    let colorous::Color { r, g, b } = colorous::RAINBOW.eval_rational(i as usize, n as usize);
-    let blend_color = if lightness > 0.5 {
-        Color32::WHITE.gamma_multiply(lightness * 2.0 - 1.0)
-    } else {
-        Color32::BLACK.gamma_multiply(1.0 - lightness * 2.0)
-    };
-    Color32::from_rgb(r, g, b).blend(blend_color)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_configs() -> impl Iterator<Item = PuzzleConfig> {
+        (2..=16).flat_map(|a| {
+            (2..=16).flat_map(move |b| {
+                let config = PuzzleConfig {
+                    a,
+                    b,
+                    ..Default::default()
+                };
+                (1..=config.max_shared()).map(move |shared| PuzzleConfig { shared, ..config })
+            })
+        })
+    }
+
+    #[test]
+    fn test_shared_slots_line_up() {
+        for config in all_configs() {
+            for i in 0..config.shared {
+                let j = config.shared_slot(i).expect("slot should be shared");
+                let (p, q) = (config.slot_center(A, i), config.slot_center(B, j));
+                assert!(
+                    (p - q).length() < 1e-4,
+                    "{config}: slot {i} at {p:?} and {q:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_config() {
+        for config in all_configs() {
+            assert_eq!(config.to_string().parse(), Ok(config));
+        }
+        assert_eq!(
+            "f3m4".parse(),
+            Ok(PuzzleConfig {
+                a: 3,
+                b: 4,
+                a_axis_stationary: true,
+                b_axis_stationary: false,
+                ..Default::default()
+            }),
+        );
+        for s in [
+            "", "M5", "M5F", "X5F2", "M1F2", "M5F17", "M5F2x0", "M5F3x2", "M8M8x3",
+        ] {
+            assert!(
+                s.parse::<PuzzleConfig>().is_err(),
+                "{s:?} should be rejected"
+            );
+        }
+    }
 }
@@ -0,0 +1,159 @@
+//! Tessellated sector and sphene meshes, cached so that drawing a frame only
+//! has to rotate and recolor them.
+
+use egui::epaint::{Mesh, Tessellator};
+use egui::*;
+
+use super::{Grip, PuzzleConfig};
+
+/// Meshes for one grip, in white, relative to the center of the grip and
+/// scaled to screen size, before rotating by [`PuzzleConfig::slot_angle()`].
+#[derive(Debug, Clone)]
+struct GripMeshes {
+    sector: Mesh,
+    sphene_fill: Mesh,
+    sphene_stroke: Mesh,
+    /// Thicker outline of a sphene, for the tracked piece.
+    tracked_stroke: Mesh,
+}
+
+#[derive(Debug, Clone)]
+pub struct GeometryCache {
+    config: PuzzleConfig,
+    scale: f32,
+    pixels_per_point: f32,
+    a: GripMeshes,
+    b: GripMeshes,
+}
+
+impl GeometryCache {
+    /// Tessellates the sectors and sphenes of `config` at `scale` points per
+    /// unit of edge length.
+    pub fn new(ctx: &Context, config: PuzzleConfig, scale: f32) -> Self {
+        let pixels_per_point = ctx.pixels_per_point();
+        let options = ctx.tessellation_options(|options| *options);
+        let mut tessellator = Tessellator::new(pixels_per_point, options, [1, 1], vec![]);
+        let mut tessellate = |shape| {
+            let mut mesh = Mesh::default();
+            tessellator.tessellate_shape(shape, &mut mesh);
+            mesh
+        };
+
+        let mut grip_meshes = |grip| {
+            let center = config.center(grip);
+            let to_local = |p: Vec2| Pos2::ZERO + (p - center) * scale;
+            let sector = config.sector_points(grip).map(to_local).collect();
+            let sphene: Vec<Pos2> = config
+                .sphene_points(grip)
+                .into_iter()
+                .map(to_local)
+                .collect();
+            GripMeshes {
+                sector: tessellate(Shape::convex_polygon(sector, Color32::WHITE, Stroke::NONE)),
+                sphene_fill: tessellate(Shape::convex_polygon(
+                    sphene.clone(),
+                    Color32::WHITE,
+                    Stroke::NONE,
+                )),
+                sphene_stroke: tessellate(Shape::closed_line(
+                    sphene.clone(),
+                    Stroke::new(0.005 * scale, Color32::WHITE),
+                )),
+                tracked_stroke: tessellate(Shape::closed_line(
+                    sphene,
+                    Stroke::new(0.03 * scale, Color32::WHITE),
+                )),
+            }
+        };
+
+        Self {
+            config,
+            scale,
+            pixels_per_point,
+            a: grip_meshes(Grip::A),
+            b: grip_meshes(Grip::B),
+        }
+    }
+
+    /// Returns whether the cache can be used to draw `config` at `scale`.
+    pub fn matches(&self, ctx: &Context, config: PuzzleConfig, scale: f32) -> bool {
+        self.config == config
+            && self.scale == scale
+            && self.pixels_per_point == ctx.pixels_per_point()
+    }
+
+    fn grip(&self, grip: Grip) -> &GripMeshes {
+        match grip {
+            Grip::A => &self.a,
+            Grip::B => &self.b,
+        }
+    }
+
+    /// Returns an empty mesh with room for all the sectors of `grip`.
+    pub fn sector_mesh(&self, grip: Grip) -> Mesh {
+        with_capacity(&self.grip(grip).sector, self.config.n(grip) as usize)
+    }
+    /// Returns an empty mesh with room for all the sphenes of `grip`.
+    pub fn sphene_mesh(&self, grip: Grip) -> Mesh {
+        let meshes = self.grip(grip);
+        let n = self.config.n(grip) as usize;
+        let mut mesh = with_capacity(&meshes.sphene_fill, n);
+        mesh.reserve_triangles(n * meshes.sphene_stroke.indices.len() / 3);
+        mesh.reserve_vertices(n * meshes.sphene_stroke.vertices.len());
+        mesh
+    }
+
+    /// Appends to `out` a sector of `grip` centered on `center` and rotated
+    /// clockwise by `angle`.
+    pub fn add_sector(&self, out: &mut Mesh, grip: Grip, center: Pos2, angle: f32, color: Color32) {
+        append_transformed(out, &self.grip(grip).sector, center, angle, color);
+    }
+
+    /// Appends to `out` a sphene of `grip` with an outline, rotated clockwise
+    /// by `angle` around `center`.
+    pub fn add_sphene(
+        &self,
+        out: &mut Mesh,
+        grip: Grip,
+        center: Pos2,
+        angle: f32,
+        fill: Color32,
+        stroke: Color32,
+    ) {
+        let meshes = self.grip(grip);
+        append_transformed(out, &meshes.sphene_fill, center, angle, fill);
+        append_transformed(out, &meshes.sphene_stroke, center, angle, stroke);
+    }
+
+    /// Returns the outline of a tracked sphene of `grip`, rotated clockwise
+    /// by `angle` around `center`.
+    pub fn tracked_outline(&self, grip: Grip, center: Pos2, angle: f32, color: Color32) -> Mesh {
+        let stroke = &self.grip(grip).tracked_stroke;
+        let mut out = with_capacity(stroke, 1);
+        append_transformed(&mut out, stroke, center, angle, color);
+        out
+    }
+}
+
+/// Returns an empty mesh with room for `count` copies of `mesh`.
+fn with_capacity(mesh: &Mesh, count: usize) -> Mesh {
+    Mesh {
+        indices: Vec::with_capacity(count * mesh.indices.len()),
+        vertices: Vec::with_capacity(count * mesh.vertices.len()),
+        texture_id: mesh.texture_id,
+    }
+}
+
+/// Appends to `out` a copy of `mesh` rotated clockwise by `angle`,
+/// translated to `center`, and tinted by `color`.
+fn append_transformed(out: &mut Mesh, mesh: &Mesh, center: Pos2, angle: f32, color: Color32) {
+    let rot = emath::Rot2::from_angle(angle);
+    let base = out.vertices.len() as u32;
+    out.indices.extend(mesh.indices.iter().map(|&i| base + i));
+    out.vertices
+        .extend(mesh.vertices.iter().map(|v| epaint::Vertex {
+            pos: center + rot * v.pos.to_vec2(),
+            uv: v.uv,
+            color: v.color * color,
+        }));
+}
@@ -0,0 +1,60 @@
+//! Shapes drawn on pieces so that they can be told apart without relying on
+//! color.
+
+use std::f32::consts::{PI, TAU};
+
+use egui::*;
+
+/// Number of distinct outlines. Each outline is drawn filled, hollow, and
+/// with a ring around it, so there are four times as many glyphs.
+const OUTLINE_COUNT: u32 = 8;
+
+/// Returns the shapes for the glyph of piece `i`, centered on `center` with
+/// radius `r`.
+pub fn glyph_shapes(i: u32, center: Pos2, r: f32, color: Color32) -> Vec<Shape> {
+    let stroke = Stroke::new(r * 0.25, color);
+    let points = outline_points(i % OUTLINE_COUNT, center, r);
+    let variant = i / OUTLINE_COUNT;
+
+    let mut shapes = vec![];
+    if variant % 2 == 0 {
+        shapes.push(Shape::convex_polygon(points, color, Stroke::NONE));
+    } else {
+        shapes.push(Shape::closed_line(points, stroke));
+    }
+    if variant >= 2 {
+        shapes.push(Shape::circle_stroke(center, r * 1.6, stroke));
+    }
+    shapes
+}
+
+fn outline_points(outline: u32, center: Pos2, r: f32) -> Vec<Pos2> {
+    // Angles are clockwise from the top.
+    let polygon = |n: u32, angle_offset: f32, r: f32| -> Vec<Pos2> {
+        (0..n)
+            .map(|i| {
+                let angle = i as f32 * TAU / n as f32 + angle_offset;
+                center + r * vec2(angle.sin(), -angle.cos())
+            })
+            .collect()
+    };
+    match outline {
+        0 => polygon(24, 0.0, r),
+        1 => polygon(3, 0.0, r * 1.15),
+        2 => polygon(3, PI, r * 1.15),
+        3 => polygon(4, PI / 4.0, r * 1.1),
+        4 => polygon(4, 0.0, r * 1.2),
+        5 => polygon(5, 0.0, r * 1.05),
+        6 => polygon(6, PI / 6.0, r * 1.05),
+        _ => {
+            let half_size = vec2(r * 1.2, r * 0.5);
+            let rect = Rect::from_center_size(center, half_size * 2.0);
+            vec![
+                rect.left_top(),
+                rect.right_top(),
+                rect.right_bottom(),
+                rect.left_bottom(),
+            ]
+        }
+    }
+}
@@ -1,11 +1,18 @@
 use serde::{Deserialize, Serialize};
 
+mod algorithm;
 mod config;
+mod geometry;
+mod glyph;
+mod solver;
 mod state;
 mod twist_anim;
 mod view;
 
+pub use algorithm::{Algorithm, Metric, Twist};
 pub use config::PuzzleConfig;
+use geometry::GeometryCache;
+pub use solver::{DistanceTable, DistanceTables, TableStatus, place_pieces};
 pub use state::PuzzleState;
 use twist_anim::{TwistAnimation, TwistAnimationState};
 pub use view::PuzzleView;
@@ -38,4 +45,11 @@ impl TwistDir {
             TwistDir::Ccw => 1.0,
         }
     }
+    #[must_use]
+    pub fn rev(self) -> TwistDir {
+        match self {
+            TwistDir::Cw => TwistDir::Ccw,
+            TwistDir::Ccw => TwistDir::Cw,
+        }
+    }
 }
@@ -0,0 +1,358 @@
+//! Optimal distances to the solved state, computed by breadth-first search.
+
+use std::collections::{HashMap, VecDeque};
+
+use web_time::{Duration, Instant};
+
+use super::{Algorithm, Grip, Metric, PuzzleConfig, PuzzleState, Twist, TwistDir};
+
+/// Maximum number of states to search before giving up.
+const MAX_STATES: u64 = 200_000;
+/// Maximum number of tables kept by [`DistanceTables`].
+const MAX_CACHED_TABLES: usize = 4;
+/// Number of states to expand between checks of the time budget in
+/// [`DistanceTables::compute()`].
+const STATES_PER_CHECK: usize = 256;
+
+/// Table of the optimal number of moves from each state to the solved state.
+#[derive(Debug, Clone)]
+pub struct DistanceTable {
+    config: PuzzleConfig,
+    metric: Metric,
+    distances: HashMap<PuzzleState, u32>,
+    /// Largest distance of any state.
+    max_distance: u32,
+}
+impl DistanceTable {
+    /// Computes the table for a puzzle, where a twist of one disk by any
+    /// amount counts as one move, or returns `None` if the puzzle has too
+    /// many states.
+    pub fn new(config: PuzzleConfig) -> Option<Self> {
+        Self::with_metric(config, Metric::Disk)
+    }
+
+    /// Computes the table for a puzzle with moves counted using `metric`, or
+    /// returns `None` if the puzzle has too many states.
+    pub fn with_metric(config: PuzzleConfig, metric: Metric) -> Option<Self> {
+        let mut search = DistanceSearch::new(config, metric)?;
+        while !search.step(None) {}
+        Some(search.finish())
+    }
+
+    pub fn config(&self) -> PuzzleConfig {
+        self.config
+    }
+    pub fn metric(&self) -> Metric {
+        self.metric
+    }
+
+    /// Returns the optimal number of moves to solve `state`.
+    pub fn distance(&self, state: &PuzzleState) -> Option<u32> {
+        self.distances.get(&state.normalized(self.config)).copied()
+    }
+
+    /// Returns the largest distance of any state (God's number).
+    pub fn max_distance(&self) -> u32 {
+        self.max_distance
+    }
+}
+
+/// Breadth-first search for a [`DistanceTable`] that can be paused, so that
+/// it can run a little at a time without blocking the UI.
+#[derive(Debug, Clone)]
+struct DistanceSearch {
+    config: PuzzleConfig,
+    metric: Metric,
+    distances: HashMap<PuzzleState, u32>,
+    /// Largest distance found so far.
+    max_distance: u32,
+    /// States whose neighbors haven't been searched yet.
+    queue: VecDeque<PuzzleState>,
+}
+impl DistanceSearch {
+    /// Starts a search, or returns `None` if the puzzle has too many states.
+    fn new(config: PuzzleConfig, metric: Metric) -> Option<Self> {
+        let bound = state_count_upper_bound(config);
+        if bound > MAX_STATES {
+            return None;
+        }
+        // Growing the map all at once up front avoids rehashing it partway
+        // through the search, which can take longer than a frame.
+        let mut distances = HashMap::with_capacity(bound as usize);
+        let solved = PuzzleState::new(config).normalized(config);
+        distances.insert(solved.clone(), 0);
+        Some(Self {
+            config,
+            metric,
+            distances,
+            max_distance: 0,
+            queue: VecDeque::from([solved]),
+        })
+    }
+
+    /// Continues the search until it is done or `deadline` has passed.
+    /// Returns whether the search is done.
+    fn step(&mut self, deadline: Option<Instant>) -> bool {
+        let config = self.config;
+        loop {
+            for _ in 0..STATES_PER_CHECK {
+                let Some(state) = self.queue.pop_front() else {
+                    return true;
+                };
+                let d = self.distances[&state];
+                for grip in [Grip::A, Grip::B] {
+                    let n = config.n(grip);
+                    let amounts = match self.metric {
+                        Metric::Disk => (1..n).collect(),
+                        Metric::Sector => vec![1, n - 1],
+                    };
+                    for amt in amounts {
+                        let mut next = state.clone();
+                        next.twist_cw(config, grip, amt);
+                        let next = next.normalized(config);
+                        if !self.distances.contains_key(&next) {
+                            self.distances.insert(next.clone(), d + 1);
+                            self.max_distance = d + 1;
+                            self.queue.push_back(next);
+                        }
+                    }
+                }
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return self.queue.is_empty();
+            }
+        }
+    }
+
+    /// Returns the table found by a finished search.
+    fn finish(&mut self) -> DistanceTable {
+        DistanceTable {
+            config: self.config,
+            metric: self.metric,
+            distances: std::mem::take(&mut self.distances),
+            max_distance: self.max_distance,
+        }
+    }
+}
+
+/// Progress of a table in [`DistanceTables`].
+#[derive(Debug, Clone)]
+enum TableEntry {
+    Computing(DistanceSearch),
+    Done(DistanceTable),
+    TooBig,
+}
+
+/// Result of looking up a table in [`DistanceTables`].
+#[derive(Debug, Copy, Clone)]
+pub enum TableStatus<'a> {
+    Ready(&'a DistanceTable),
+    /// The table is being computed by [`DistanceTables::compute()`].
+    Computing,
+    /// The puzzle has too many states.
+    TooBig,
+}
+impl<'a> TableStatus<'a> {
+    /// Returns the table if it is ready.
+    pub fn ready(self) -> Option<&'a DistanceTable> {
+        match self {
+            TableStatus::Ready(table) => Some(table),
+            TableStatus::Computing | TableStatus::TooBig => None,
+        }
+    }
+}
+
+/// Cache of the most recently used distance tables, so that each table is
+/// only computed once instead of whenever it is needed.
+///
+/// Tables are computed a little at a time by [`Self::compute()`], which
+/// should be called once per frame, because computing a whole table can
+/// take longer than a frame.
+#[derive(Debug, Default, Clone)]
+pub struct DistanceTables {
+    /// Tables along with the configuration and metric they were computed
+    /// for, least recently requested first.
+    tables: VecDeque<(PuzzleConfig, Metric, TableEntry)>,
+}
+impl DistanceTables {
+    /// Returns the table for a puzzle and metric, starting to compute it if
+    /// necessary.
+    pub fn get(&mut self, config: PuzzleConfig, metric: Metric) -> TableStatus<'_> {
+        let i = match self
+            .tables
+            .iter()
+            .position(|(c, m, _)| *c == config && *m == metric)
+        {
+            Some(i) => i,
+            None => {
+                if self.tables.len() >= MAX_CACHED_TABLES {
+                    self.tables.pop_front();
+                }
+                let entry = match DistanceSearch::new(config, metric) {
+                    Some(search) => TableEntry::Computing(search),
+                    None => TableEntry::TooBig,
+                };
+                self.tables.push_back((config, metric, entry));
+                self.tables.len() - 1
+            }
+        };
+        match &self.tables[i].2 {
+            TableEntry::Computing(_) => TableStatus::Computing,
+            TableEntry::Done(table) => TableStatus::Ready(table),
+            TableEntry::TooBig => TableStatus::TooBig,
+        }
+    }
+
+    /// Continues computing the tables that have been requested, for at most
+    /// about `budget`. Returns whether any tables are still being computed.
+    pub fn compute(&mut self, budget: Duration) -> bool {
+        let deadline = Instant::now().checked_add(budget);
+        for (_, _, entry) in &mut self.tables {
+            let TableEntry::Computing(search) = entry else {
+                continue;
+            };
+            if !search.step(deadline) {
+                return true;
+            }
+            *entry = TableEntry::Done(search.finish());
+        }
+        false
+    }
+}
+
+/// Returns twists that bring `pieces` to their home slots from `state`,
+/// ignoring all other pieces, or `None` if it would take too long to find
+/// them. The pieces are placed one at a time without disturbing the ones
+/// before, so the result is short but not necessarily optimal.
+pub fn place_pieces(
+    config: PuzzleConfig,
+    state: &PuzzleState,
+    pieces: &[u32],
+) -> Option<Algorithm> {
+    let solved = PuzzleState::new(config);
+    let mut state = state.clone();
+    let mut twists = vec![];
+    for j in 1..=pieces.len() {
+        let placed = &pieces[..j];
+        let positions = |state: &PuzzleState| -> Vec<Option<(Grip, u32)>> {
+            placed.iter().map(|&p| state.find_piece(p)).collect()
+        };
+        let home = positions(&solved);
+
+        // Breadth-first search over the positions of the placed pieces,
+        // remembering the twist that first reached each one.
+        let start = positions(&state);
+        let mut parents = HashMap::from([(start.clone(), None)]);
+        let mut queue = VecDeque::from([state.clone()]);
+        let mut found = start == home;
+        while !found {
+            if parents.len() as u64 > MAX_STATES {
+                return None;
+            }
+            let current = queue.pop_front()?;
+            let current_positions = positions(&current);
+            for grip in [Grip::A, Grip::B] {
+                for amt in 1..config.n(grip) {
+                    let mut next = current.clone();
+                    next.twist_cw(config, grip, amt);
+                    let next_positions = positions(&next);
+                    if parents.contains_key(&next_positions) {
+                        continue;
+                    }
+                    let twist = Twist {
+                        grip,
+                        dir: TwistDir::Cw,
+                        amt,
+                    };
+                    found |= next_positions == home;
+                    parents.insert(next_positions, Some((current_positions.clone(), twist)));
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        let mut path = vec![];
+        let mut key = home;
+        while let Some(Some((parent, twist))) = parents.get(&key) {
+            path.push(*twist);
+            key = parent.clone();
+        }
+        path.reverse();
+        for twist in path {
+            twist.apply(config, &mut state);
+            twists.push(twist);
+        }
+    }
+    Some(Algorithm(twists).simplified(config))
+}
+
+/// Returns an upper bound for the number of reachable states, saturating at
+/// `u64::MAX`.
+fn state_count_upper_bound(config: PuzzleConfig) -> u64 {
+    let mut ret = (1..=config.piece_count() as u64).fold(1_u64, u64::saturating_mul);
+    for grip in [Grip::A, Grip::B] {
+        if config.supercube && !config.axis_stationary(grip) {
+            ret = ret.saturating_mul(config.n(grip) as u64);
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_tables() {
+        let config: PuzzleConfig = "M4F2".parse().expect("invalid puzzle");
+        let mut tables = DistanceTables::default();
+        assert!(matches!(
+            tables.get(config, Metric::Disk),
+            TableStatus::Computing
+        ));
+        assert!(!tables.compute(Duration::MAX));
+        let table = tables
+            .get(config, Metric::Disk)
+            .ready()
+            .expect("puzzle too big");
+        let max = table.distances.values().copied().max();
+        assert_eq!(Some(table.max_distance()), max);
+        assert_eq!(table.distance(&PuzzleState::new(config)), Some(0));
+
+        for metric in [Metric::Disk, Metric::Sector] {
+            tables.get(config, metric);
+            tables.compute(Duration::MAX);
+            for _ in 0..2 {
+                let table = tables.get(config, metric).ready().expect("puzzle too big");
+                assert_eq!(table.metric(), metric);
+            }
+        }
+        assert_eq!(tables.tables.len(), 2);
+
+        let big: PuzzleConfig = "M16M16".parse().expect("invalid puzzle");
+        assert!(matches!(tables.get(big, Metric::Disk), TableStatus::TooBig));
+        assert!(!tables.compute(Duration::MAX));
+    }
+
+    #[test]
+    fn test_place_pieces() {
+        use rand::SeedableRng;
+
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(1);
+        for puzzle in ["M5F2", "M4M4x2", "F6M3", "M8F8"] {
+            let config: PuzzleConfig = puzzle.parse().expect("invalid puzzle");
+            for pieces in [vec![0], vec![0, 1, 2], vec![3, 1], (0..config.a).collect()] {
+                let mut state = PuzzleState::random(config, &mut rng);
+                let alg = place_pieces(config, &state, &pieces).expect("no solution");
+                alg.apply(config, &mut state);
+                for &p in &pieces {
+                    assert!(state.is_piece_home(config, p), "{puzzle}: {p} not placed");
+                }
+
+                // Nothing to do once the pieces are placed.
+                let again = place_pieces(config, &state, &pieces).expect("no solution");
+                assert_eq!(again, Algorithm::default());
+            }
+        }
+    }
+}
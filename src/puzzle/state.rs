@@ -1,8 +1,9 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use super::{Grip, PuzzleConfig};
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub struct PuzzleState {
     pub a_rot: u32,
     pub b_rot: u32,
@@ -12,9 +13,10 @@ pub struct PuzzleState {
 
 impl PuzzleState {
     pub fn new(config: PuzzleConfig) -> Self {
-        let a_pieces = (0..).take(config.a as usize).collect();
-        let mut b_pieces: Vec<_> = (config.a - 1..).take(config.b as usize).collect();
-        b_pieces[0] = 0;
+        let a_pieces = (0..config.a).collect();
+        let b_pieces = (0..config.b)
+            .map(|i| config.color_index_in_grip(Grip::B, i))
+            .collect();
         Self {
             a_rot: 0,
             b_rot: 0,
@@ -23,6 +25,16 @@ impl PuzzleState {
         }
     }
 
+    /// Returns a randomly scrambled state.
+    pub fn random(config: PuzzleConfig, rng: &mut impl Rng) -> Self {
+        let mut state = Self::new(config);
+        for _ in 0..500 {
+            state.twist_cw(config, Grip::A, rng.random_range(0..state.n(Grip::A)));
+            state.twist_cw(config, Grip::B, rng.random_range(0..state.n(Grip::B)));
+        }
+        state
+    }
+
     pub fn rot(&self, grip: Grip) -> u32 {
         match grip {
             Grip::A => self.a_rot,
@@ -49,39 +61,90 @@ impl PuzzleState {
         }
     }
 
+    /// Returns the grip and slot containing piece `p`. Pieces in a shared slot
+    /// are reported on [`Grip::A`].
+    pub fn find_piece(&self, p: u32) -> Option<(Grip, u32)> {
+        [Grip::A, Grip::B].into_iter().find_map(|grip| {
+            let i = self.pieces(grip).iter().position(|&q| q == p)?;
+            Some((grip, i as u32))
+        })
+    }
+
     pub fn n(&self, grip: Grip) -> u32 {
         self.pieces(grip).len() as u32
     }
 
-    pub fn twist_ccw(&mut self, grip: Grip, amt: u32) {
+    pub fn twist_ccw(&mut self, config: PuzzleConfig, grip: Grip, amt: u32) {
         let n = self.n(grip);
         let amt = amt.rem_euclid(n);
         *self.rot_mut(grip) = (self.rot(grip) + amt) % n;
         self.pieces_mut(grip).rotate_left(amt as usize);
-        self.set_shared_piece(self.pieces(grip)[0]);
+        self.sync_shared_pieces(config, grip);
     }
-    pub fn twist_cw(&mut self, grip: Grip, amt: u32) {
+    pub fn twist_cw(&mut self, config: PuzzleConfig, grip: Grip, amt: u32) {
         let n = self.n(grip);
         let amt = amt.rem_euclid(n);
         *self.rot_mut(grip) = (self.rot(grip) + n - amt) % n;
         self.pieces_mut(grip).rotate_right(amt as usize);
-        self.set_shared_piece(self.pieces(grip)[0]);
+        self.sync_shared_pieces(config, grip);
     }
 
-    fn set_shared_piece(&mut self, p: u32) {
-        self.a_pieces[0] = p;
-        self.b_pieces[0] = p;
+    /// Copies the pieces in the shared slots of `grip` to the other disk.
+    fn sync_shared_pieces(&mut self, config: PuzzleConfig, grip: Grip) {
+        let k = config.shared as usize;
+        for i in 0..k {
+            let p = self.pieces(grip)[i];
+            self.pieces_mut(grip.other())[k - 1 - i] = p;
+        }
     }
 
-    pub fn is_solved(&self, config: PuzzleConfig) -> bool {
+    /// Returns the rotation of the sectors of `grip` relative to their home
+    /// position, as the shortest signed number of steps counterclockwise.
+    pub fn rot_offset(&self, grip: Grip) -> i32 {
+        let n = self.n(grip) as i32;
+        let rot = self.rot(grip) as i32;
+        if rot * 2 > n { rot - n } else { rot }
+    }
+
+    /// Returns whether every piece is in its home slot, regardless of the
+    /// orientation of the sectors.
+    pub fn are_pieces_solved(&self, config: PuzzleConfig) -> bool {
         let init = Self::new(config);
-        let mut this = self.clone();
-        if config.a_axis_stationary {
-            this.a_rot = 0;
+        self.a_pieces == init.a_pieces && self.b_pieces == init.b_pieces
+    }
+
+    /// Returns whether piece `p` is in its home slot.
+    pub fn is_piece_home(&self, config: PuzzleConfig, p: u32) -> bool {
+        self.find_piece(p) == Self::new(config).find_piece(p)
+    }
+    /// Returns the number of pieces that are in their home slot.
+    pub fn pieces_home(&self, config: PuzzleConfig) -> u32 {
+        let init = Self::new(config);
+        (0..config.piece_count())
+            .filter(|&p| self.find_piece(p) == init.find_piece(p))
+            .count() as u32
+    }
+
+    /// Returns a copy of the state with the rotation of each axis zeroed if it
+    /// does not matter for whether the puzzle is solved.
+    pub fn normalized(&self, config: PuzzleConfig) -> Self {
+        let mut ret = self.clone();
+        if !config.supercube || config.a_axis_stationary {
+            ret.a_rot = 0;
         }
-        if config.b_axis_stationary {
-            this.b_rot = 0;
+        if !config.supercube || config.b_axis_stationary {
+            ret.b_rot = 0;
         }
-        this == init
+        ret
+    }
+
+    /// Returns whether the puzzle is solved. In supercube mode, the sectors of
+    /// each moving axis must also be in their home orientation.
+    pub fn is_solved(&self, config: PuzzleConfig) -> bool {
+        self.are_pieces_solved(config)
+            && (!config.supercube
+                || [Grip::A, Grip::B]
+                    .into_iter()
+                    .all(|g| config.axis_stationary(g) || self.rot(g) == 0))
     }
 }
@@ -4,32 +4,47 @@ use std::collections::VecDeque;
 
 use web_time::Duration;
 
-use super::{Grip, PuzzleState};
+use super::{PuzzleConfig, PuzzleState, Twist};
 use crate::Preferences;
 
 /// If at least this much of a twist is animated in one frame, just skip the
 /// animation to reduce unnecessary flashing.
 const MIN_TWIST_DELTA: f32 = 1.0 / 3.0;
 
-/// Higher number means faster exponential increase in twist speed.
-const EXP_TWIST_FACTOR: f32 = 0.2;
-
-/// Whether to speed up twists if there are more in the queue.
-const DYNAMIC_TWIST_SPEED: bool = true;
-
 #[derive(Debug, Default, Clone)]
 pub struct TwistAnimationState {
     /// Queue of twist animations to be displayed.
     queue: VecDeque<TwistAnimation>,
+    /// Puzzle state before the first twist in the queue. Only meaningful
+    /// while the queue is non-empty; completed twists are applied to it one
+    /// at a time, so the queue itself only needs to store moves.
+    displayed_state: Option<PuzzleState>,
+    /// Twists removed from the queue by [`Self::cancel_pending()`], which
+    /// come right after the first twist in the queue. They are applied to
+    /// `displayed_state` without animation when that twist finishes.
+    skipped: Vec<Twist>,
+    /// Whether the first twist in the queue is being animated backwards,
+    /// because its inverse was queued while it was in progress.
+    reversing: bool,
     /// Maximum number of animations in the queue (reset when queue is empty).
     queue_max: usize,
     /// Progress of the animation in the current twist, from 0.0 to 1.0.
     progress: f32,
 }
 impl TwistAnimationState {
-    /// Steps the animation forward. Returns whether the puzzle should be
-    /// redrawn next frame.
-    pub fn proceed(&mut self, delta: Duration, prefs: &Preferences) -> bool {
+    /// Steps the animation forward, or instantly completes all twists if
+    /// `animate` is false. Returns whether the puzzle should be redrawn next
+    /// frame.
+    pub fn proceed(
+        &mut self,
+        delta: Duration,
+        config: PuzzleConfig,
+        prefs: &Preferences,
+        animate: bool,
+    ) -> bool {
+        if !animate {
+            self.finish_all();
+        }
         if self.queue.is_empty() {
             self.queue_max = 0;
             false // Do not request redraw
@@ -40,8 +55,8 @@ impl TwistAnimationState {
 
             // Twist exponentially faster if there are/were more twists in the
             // queue.
-            let speed_mod = match DYNAMIC_TWIST_SPEED {
-                true => ((self.queue_max - 1) as f32 * EXP_TWIST_FACTOR).exp(),
+            let speed_mod = match prefs.dynamic_twist_speed {
+                true => ((self.queue_max - 1) as f32 * prefs.twist_speed_up_factor).exp(),
                 false => 1.0,
             };
             let mut twist_delta = base_speed * speed_mod;
@@ -52,37 +67,177 @@ impl TwistAnimationState {
                 twist_delta = 1.0; // Instantly complete the twist.
             }
 
-            self.progress += twist_delta;
-            if self.progress >= 1.0 {
-                self.progress = 0.0;
-                self.queue.pop_front();
+            if self.reversing {
+                self.progress -= twist_delta;
+                if self.progress <= 0.0 {
+                    // The twist and its inverse cancel out, so the displayed
+                    // state is already correct.
+                    self.progress = 0.0;
+                    self.reversing = false;
+                    self.queue.pop_front();
+                    self.apply_skipped(config);
+                }
+            } else {
+                self.progress += twist_delta;
+                if self.progress >= 1.0 {
+                    self.progress = 0.0;
+                    if let (Some(anim), Some(state)) =
+                        (self.queue.pop_front(), &mut self.displayed_state)
+                    {
+                        anim.twist.apply(config, state);
+                    }
+                    self.apply_skipped(config);
+                }
             }
 
             true // Request redraw
         }
     }
 
-    pub fn push(&mut self, anim: TwistAnimation) {
+    /// Queues a twist animation, given the puzzle state before the twist.
+    ///
+    /// If the inverse of `anim` is the last twist in the queue, the two
+    /// cancel out instead: a twist that hasn't started yet is removed, and a
+    /// twist in progress is animated backwards from its current angle.
+    pub fn push(&mut self, state_before: &PuzzleState, anim: TwistAnimation) {
+        if self.queue.is_empty() {
+            match &mut self.displayed_state {
+                // Reuse the existing allocation.
+                Some(state) => state.clone_from(state_before),
+                None => self.displayed_state = Some(state_before.clone()),
+            }
+            self.reversing = false;
+            self.skipped.clear();
+        } else if anim.initial_angle == 0.0 {
+            let is_inverse_of = |other: &TwistAnimation| {
+                anim.twist
+                    == Twist {
+                        dir: other.twist.dir.rev(),
+                        ..other.twist
+                    }
+            };
+            // Skipped twists come between the current twist and `anim`, so
+            // the two only cancel out if there are none.
+            if self.queue.len() == 1 && self.skipped.is_empty() {
+                let current = &self.queue[0];
+                if !self.reversing && is_inverse_of(current) {
+                    self.reversing = true;
+                    return;
+                } else if self.reversing && anim.twist == current.twist {
+                    self.reversing = false;
+                    return;
+                }
+            } else if self.queue.len() > 1 && self.queue.back().is_some_and(is_inverse_of) {
+                self.queue.pop_back();
+                return;
+            }
+        }
+
         self.queue.push_back(anim);
 
         // Update queue_max.
         self.queue_max = std::cmp::max(self.queue_max, self.queue.len());
     }
 
+    /// Instantly completes all queued twists.
+    pub fn finish_all(&mut self) {
+        self.queue.clear();
+        self.skipped.clear();
+        self.reversing = false;
+        self.progress = 0.0;
+    }
+
+    /// Removes all twists after the current one from the queue, so that only
+    /// the current twist keeps animating. The removed twists still apply to
+    /// the puzzle; they just aren't animated.
+    pub fn cancel_pending(&mut self) {
+        let pending = self.queue.iter().skip(1).map(|anim| anim.twist);
+        self.skipped.extend(pending);
+        self.queue.truncate(1);
+    }
+
+    /// Applies the twists removed by [`Self::cancel_pending()`] once the
+    /// twist before them has finished.
+    fn apply_skipped(&mut self, config: PuzzleConfig) {
+        if let Some(state) = &mut self.displayed_state {
+            for twist in self.skipped.drain(..) {
+                twist.apply(config, state);
+            }
+        }
+    }
+
+    /// Sets the progress of the current twist, from 0.0 to 1.0.
+    pub fn set_progress(&mut self, progress: f32) {
+        self.progress = progress;
+    }
+
     pub fn current(&self) -> Option<(&TwistAnimation, f32)> {
         Some((self.queue.front()?, self.progress))
     }
+
+    /// Returns the puzzle state that the current twist animation starts
+    /// from, or `None` if there is no animation.
+    pub fn displayed_state(&self) -> Option<&PuzzleState> {
+        self.displayed_state
+            .as_ref()
+            .filter(|_| !self.queue.is_empty())
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Copy, Clone)]
 pub struct TwistAnimation {
-    /// Puzzle state before the twist.
-    pub state: PuzzleState,
-    /// Set of pieces affected by the twist.
-    pub grip: Grip,
+    /// Twist being animated. Its grip is the set of pieces affected.
+    pub twist: Twist,
     /// Initial angle of the gripped pieces (zero, unless the move was inputted
     /// using a mouse drag).
     pub initial_angle: f32,
     /// Final angle for the the gripped pieces.
     pub final_angle: f32,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle::{Grip, TwistDir};
+
+    fn push(anims: &mut TwistAnimationState, state: &mut PuzzleState, grip: Grip, dir: TwistDir) {
+        let twist = Twist { grip, dir, amt: 1 };
+        let anim = TwistAnimation {
+            twist,
+            initial_angle: 0.0,
+            final_angle: 1.0,
+        };
+        anims.push(state, anim);
+        twist.apply(PuzzleConfig::default(), state);
+    }
+
+    #[test]
+    fn test_cancel_pending_keeps_displayed_state() {
+        let prefs = Preferences::default();
+        let frame = Duration::from_secs(1);
+        let mut anims = TwistAnimationState::default();
+        let config = PuzzleConfig::default();
+        let mut state = PuzzleState::new(config);
+
+        push(&mut anims, &mut state, Grip::A, TwistDir::Cw);
+        push(&mut anims, &mut state, Grip::B, TwistDir::Cw);
+        anims.cancel_pending();
+        // Not the inverse of the current twist anymore, because the skipped
+        // twist comes between them.
+        push(&mut anims, &mut state, Grip::A, TwistDir::Ccw);
+
+        anims.proceed(frame, config, &prefs, true);
+        let mut expected = state.clone();
+        Twist {
+            grip: Grip::A,
+            dir: TwistDir::Cw,
+            amt: 1,
+        }
+        .apply(config, &mut expected);
+        assert_eq!(anims.displayed_state(), Some(&expected));
+
+        anims.proceed(frame, config, &prefs, true);
+        assert_eq!(anims.displayed_state(), None);
+        assert!(!anims.proceed(frame, config, &prefs, true));
+    }
+}
@@ -1,69 +1,435 @@
 use std::{
+    collections::VecDeque,
     f32::consts::{PI, TAU},
-    hash::{DefaultHasher, Hash, Hasher},
 };
 
 use egui::*;
-use rand::{Rng, SeedableRng};
 use web_time::{Duration, Instant};
 
-use super::{Grip, PuzzleConfig, PuzzleState, TwistAnimation, TwistAnimationState, TwistDir};
+use super::{
+    DistanceTables, GeometryCache, Grip, Metric, PuzzleConfig, PuzzleState, TableStatus, Twist,
+    TwistAnimation, TwistAnimationState, TwistDir,
+};
 use crate::Preferences;
+use crate::export::{SvgOptions, TemplateOptions};
+use crate::util::{rotate_point, time_seeded_rng};
 
 const ASSUMED_FPS: f32 = 120.0;
+/// Time spent computing distance tables each frame, short enough to keep
+/// the frame rate up.
+const DISTANCE_TABLE_BUDGET: Duration = Duration::from_millis(4);
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 8.0;
+
+/// Extra distance around each disk that counts as hovering it in touch mode,
+/// in units of edge length.
+const TOUCH_HIT_MARGIN: f32 = 0.15;
+/// Rotation of two fingers, in radians, after which the gesture twists a
+/// disk.
+const GESTURE_ROTATION_THRESHOLD: f32 = 0.25;
+/// Zoom factor of two fingers after which the gesture zooms the view.
+const GESTURE_ZOOM_THRESHOLD: f32 = 1.15;
+/// Movement of two fingers, in points, after which the gesture pans the view.
+const GESTURE_TRANSLATION_THRESHOLD: f32 = 30.0;
+/// Maximum duration of a drag, in seconds, for it to count as a swipe.
+const SWIPE_MAX_DURATION: f64 = 0.3;
+/// Minimum angle of a swipe, in radians.
+const SWIPE_MIN_ANGLE: f32 = 0.05;
+/// Minimum distance of the start of a swipe from the center of the disk,
+/// relative to its radius.
+const SWIPE_MIN_RADIUS: f32 = 0.6;
+
+/// Time window, in seconds, over which drag velocity is measured.
+const DRAG_VELOCITY_WINDOW: f64 = 0.1;
+/// How long, in seconds, a released disk coasts at its release velocity
+/// before snapping.
+const INERTIA_COAST_TIME: f32 = 0.15;
+
+/// Recent angles of a dragged disk, used to measure its angular velocity.
+#[derive(Debug, Default, Clone)]
+struct DragVelocity {
+    /// Time in seconds and angle of each sample, oldest first.
+    samples: VecDeque<(f64, f32)>,
+}
+impl DragVelocity {
+    fn clear(&mut self) {
+        self.samples.clear();
+    }
+    fn push(&mut self, time: f64, angle: f32) {
+        self.samples.push_back((time, angle));
+        while self
+            .samples
+            .front()
+            .is_some_and(|&(t, _)| t < time - DRAG_VELOCITY_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+    }
+    /// Returns the angular velocity in radians per second, as of `now`.
+    fn velocity(&self, now: f64) -> f32 {
+        let recent = || {
+            self.samples
+                .iter()
+                .filter(|&&(t, _)| t >= now - DRAG_VELOCITY_WINDOW)
+        };
+        let Some(&(start_time, _)) = recent().next() else {
+            return 0.0;
+        };
+        let dt = (now - start_time) as f32;
+        if dt <= 0.0 {
+            return 0.0;
+        }
+        // Sum the wrapped differences so that crossing the branch cut of
+        // `angle()` doesn't look like a full turn.
+        let angle: f32 = recent()
+            .zip(recent().skip(1))
+            .map(|((_, a), (_, b))| (b - a + PI).rem_euclid(TAU) - PI)
+            .sum();
+        angle / dt
+    }
+}
+
+/// Two-finger gesture on the puzzle.
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum TouchGesture {
+    /// The fingers haven't moved enough to tell what the gesture is.
+    Undecided {
+        rotation: f32,
+        zoom: f32,
+        translation: Vec2,
+    },
+    /// Rotating the fingers twists a disk.
+    Twist(Grip),
+    /// Pinching and moving the fingers zooms and pans the view.
+    View,
+}
+
+/// Zoom, pan, and rotation of the puzzle, relative to fitting it in the
+/// available space.
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct Viewport {
+    zoom: f32,
+    /// Offset of the puzzle from the center of the available space, in
+    /// points.
+    pan: Vec2,
+    /// Clockwise rotation of the whole puzzle, in radians.
+    rotation: f32,
+}
+
+impl Viewport {
+    fn is_fit(&self) -> bool {
+        self.zoom == 1.0 && self.pan == Vec2::ZERO
+    }
+    /// Resets zoom and pan, keeping rotation.
+    fn fit(&mut self) {
+        self.zoom = 1.0;
+        self.pan = Vec2::ZERO;
+    }
+}
+
+impl Default for Viewport {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+            rotation: 0.0,
+        }
+    }
+}
+
+/// Mapping from puzzle coordinates to screen coordinates.
+#[derive(Debug, Copy, Clone)]
+struct ScreenTransform {
+    /// Screen position of the puzzle-space origin.
+    origin: Pos2,
+    /// Number of points per unit of edge length.
+    scale: f32,
+    /// Clockwise rotation, in radians.
+    rotation: f32,
+}
+
+impl ScreenTransform {
+    fn to_screen(self, p: Vec2) -> Pos2 {
+        self.origin + emath::Rot2::from_angle(self.rotation) * (p * self.scale)
+    }
+    fn to_puzzle(self, p: Pos2) -> Vec2 {
+        emath::Rot2::from_angle(-self.rotation) * (p - self.origin) / self.scale
+    }
+}
 
 #[derive(Debug, Default, Clone)]
 pub struct PuzzleView {
     config: PuzzleConfig,
     state: Option<PuzzleState>,
     was_scrambled: bool,
+    /// Twists applied since the puzzle was last reset or scrambled.
+    history: Vec<Twist>,
 
     animation: TwistAnimationState,
     last_frame_time: Option<Instant>,
+    /// Whether the twist animation only moves when its progress is set
+    /// explicitly, for rendering frames without a clock.
+    animation_frozen: bool,
+
+    viewport: Viewport,
 
     drag_start: Option<Pos2>,
+    /// Time when the current drag started, in seconds.
+    drag_start_time: f64,
     drag_angle_offset: Option<(Grip, f32)>,
+    drag_velocity: DragVelocity,
+    touch_gesture: Option<TouchGesture>,
+
+    /// Tessellated geometry for the current configuration and scale.
+    geometry: Option<GeometryCache>,
+
+    /// Whether to hide the pieces, for blindfolded solving.
+    blindfolded: bool,
+    /// Whether twisting is disabled, such as before a timed solve starts.
+    twists_locked: bool,
+
+    /// Piece marked by the user to follow it around the puzzle.
+    tracked_piece: Option<u32>,
+    /// Slots visited by the tracked piece since it was marked.
+    tracked_trail: Vec<(Grip, u32)>,
+
+    /// Distance tables computed so far.
+    distance_tables: DistanceTables,
+
+    svg_options: SvgOptions,
+    template_options: TemplateOptions,
+    export_solved: bool,
 }
 
 impl PuzzleView {
+    pub fn new(config: PuzzleConfig, state: PuzzleState) -> Self {
+        Self {
+            config,
+            state: Some(state),
+            ..Default::default()
+        }
+    }
+
+    pub fn config(&self) -> PuzzleConfig {
+        self.config
+    }
+
     pub fn reset(&mut self) {
         self.state = None;
         self.animation = TwistAnimationState::default();
         self.was_scrambled = false;
+        self.history.clear();
+        self.tracked_trail.clear();
     }
 
     pub fn scramble(&mut self) {
+        let mut rng = time_seeded_rng();
+        self.scramble_to(self.config, PuzzleState::random(self.config, &mut rng));
+    }
+
+    /// Resets the puzzle and sets it to a given configuration and scrambled
+    /// state.
+    pub fn scramble_to(&mut self, config: PuzzleConfig, state: PuzzleState) {
         self.reset();
-        let state = self.state.insert(PuzzleState::new(self.config));
-
-        // this is awful seeding but it's fine for this puzzle and I couldn't
-        // get `getrandom` to work on web
-        let mut h = DefaultHasher::new();
-        Instant::now().hash(&mut h);
-        let bytes = h.finish().to_ne_bytes();
-        let mut rng = rand::rngs::StdRng::from_seed(
-            [bytes; 4]
-                .as_flattened()
-                .try_into()
-                .expect("error casting [[u8; 8]; 4] to [u8; 32]"),
-        );
-        for _ in 0..500 {
-            state.twist_cw(Grip::A, rng.random_range(0..state.n(Grip::A)));
-            state.twist_cw(Grip::B, rng.random_range(0..state.n(Grip::B)));
-        }
+        self.config = config;
+        self.state = Some(state);
 
         self.was_scrambled = true;
+        self.restart_tracked_trail();
     }
 
     pub fn was_scrambled(&self) -> bool {
         self.was_scrambled
     }
+    /// Returns the twists applied since the puzzle was last reset or
+    /// scrambled.
+    pub fn history(&self) -> &[Twist] {
+        &self.history
+    }
+    /// Hides or shows the pieces. Sectors stay visible so that the
+    /// orientation of each disk can still be seen.
+    pub fn set_blindfolded(&mut self, blindfolded: bool) {
+        self.blindfolded = blindfolded;
+    }
+    pub fn is_blindfolded(&self) -> bool {
+        self.blindfolded
+    }
+    /// Disables or enables twisting the puzzle.
+    pub fn set_twists_locked(&mut self, locked: bool) {
+        self.twists_locked = locked;
+    }
+
+    /// Returns whether the puzzle has been scrambled and not yet solved.
+    pub fn is_solving(&self) -> bool {
+        self.was_scrambled && !self.is_solved()
+    }
     pub fn is_solved(&self) -> bool {
         self.state
             .as_ref()
             .is_none_or(|state| state.is_solved(self.config))
     }
 
+    /// Marks the piece at `cursor` for tracking, or unmarks it if it was
+    /// already marked.
+    fn toggle_tracked_piece(&mut self, cursor: Vec2) {
+        if self.blindfolded {
+            return;
+        }
+        let Some(state) = &self.state else { return };
+        let cfg = self.config;
+        let piece = [Grip::A, Grip::B].into_iter().find_map(|grip| {
+            let i = cfg.hovered_slot(grip, cursor)?;
+            Some(state.pieces(grip)[i as usize])
+        });
+        if piece.is_some() && piece != self.tracked_piece {
+            self.tracked_piece = piece;
+        } else {
+            self.tracked_piece = None;
+        }
+        self.restart_tracked_trail();
+    }
+
+    fn restart_tracked_trail(&mut self) {
+        self.tracked_trail.clear();
+        self.record_tracked_position();
+    }
+
+    fn record_tracked_position(&mut self) {
+        let pos = Option::zip(self.state.as_ref(), self.tracked_piece)
+            .and_then(|(state, piece)| state.find_piece(piece));
+        if let Some(pos) = pos {
+            if self.tracked_trail.last() != Some(&pos) {
+                self.tracked_trail.push(pos);
+            }
+        }
+    }
+
+    /// Returns the distance tables computed so far, which are shared with
+    /// the challenges that show optimal move counts.
+    pub fn distance_tables(&mut self) -> &mut DistanceTables {
+        &mut self.distance_tables
+    }
+
+    pub fn show_progress(&mut self, ui: &mut Ui, prefs: &Preferences) {
+        let cfg = self.config;
+        let state = self
+            .state
+            .get_or_insert_with(|| PuzzleState::new(cfg))
+            .clone();
+
+        ui.horizontal_wrapped(|ui| {
+            let home = state.pieces_home(cfg);
+            ui.label(format!("Pieces in place: {home}/{}", cfg.piece_count()));
+
+            if prefs.show_distance_to_solved {
+                ui.separator();
+                match self.distance_tables.get(cfg, Metric::Disk) {
+                    TableStatus::Ready(table) => {
+                        let max = table.max_distance();
+                        match table.distance(&state) {
+                            Some(d) => ui.label(format!("Distance to solved: {d}/{max}")),
+                            None => ui.label("Distance to solved: unknown"),
+                        };
+                    }
+                    TableStatus::Computing => {
+                        ui.label("Distance to solved: computing…");
+                    }
+                    TableStatus::TooBig => {
+                        ui.label("Distance to solved: puzzle too big")
+                            .on_hover_text("Distances are only computed for small puzzles");
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn show_export(&mut self, ui: &mut Ui, prefs: &Preferences) {
+        let cfg = self.config;
+        let opts = &mut self.svg_options;
+
+        ui.horizontal(|ui| {
+            ui.add_enabled_ui(!self.blindfolded, |ui| {
+                ui.selectable_value(&mut self.export_solved, false, "Current")
+                    .on_disabled_hover_text("Hidden while blindfolded");
+            });
+            ui.selectable_value(&mut self.export_solved, true, "Solved");
+            ui.separator();
+            ui.selectable_value(&mut opts.dark_mode, true, "Dark");
+            ui.selectable_value(&mut opts.dark_mode, false, "Light");
+        });
+        if ui.button("Copy SVG").clicked() {
+            let state = match (self.export_solved || self.blindfolded, &self.state) {
+                (false, Some(state)) => state.clone(),
+                _ => PuzzleState::new(cfg),
+            };
+            let options = SvgOptions {
+                colors: prefs.colors.clone(),
+                labels: prefs.labels.clone(),
+                ..opts.clone()
+            };
+            let svg = crate::export::export_svg(cfg, &state, &options);
+            ui.ctx().copy_text(svg);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Edge length");
+            ui.add(
+                DragValue::new(&mut self.template_options.edge_length_mm)
+                    .range(5.0..=200.0)
+                    .suffix(" mm"),
+            );
+        });
+        if ui.button("Copy cut template SVG").clicked() {
+            let template_options = TemplateOptions {
+                labels: prefs.labels.clone(),
+                ..self.template_options.clone()
+            };
+            let svg = crate::export::export_template(cfg, &template_options);
+            ui.ctx().copy_text(svg);
+        }
+    }
+
+    pub fn show_tracking(&mut self, ui: &mut Ui, prefs: &Preferences) {
+        let cfg = self.config;
+        let state = self.state.get_or_insert_with(|| PuzzleState::new(cfg));
+
+        if self.blindfolded {
+            ui.label("Piece tracking is hidden while blindfolded");
+            return;
+        }
+        let Some(piece) = self.tracked_piece else {
+            ui.label("Ctrl+click a piece to track it");
+            return;
+        };
+
+        let describe_slot = |(grip, i): (Grip, u32)| {
+            if cfg.shared_slot(i).is_some() && grip == Grip::A {
+                format!("center, slot {}", i + 1)
+            } else {
+                match grip {
+                    Grip::A => format!("left disk, slot {}", i + 1),
+                    Grip::B => format!("right disk, slot {}", i + 1),
+                }
+            }
+        };
+
+        ui.horizontal(|ui| {
+            let name = prefs.labels.sticker_name(cfg, piece);
+            ui.label(format!("Tracking piece {name}"));
+            if ui.button("Clear").clicked() {
+                self.tracked_piece = None;
+                self.tracked_trail.clear();
+            }
+        });
+        if let Some(pos) = state.find_piece(piece) {
+            ui.label(format!("Current position: {}", describe_slot(pos)));
+        }
+        if let Some(pos) = PuzzleState::new(cfg).find_piece(piece) {
+            ui.label(format!("Home position: {}", describe_slot(pos)));
+        }
+    }
+
     pub fn show_config(&mut self, ui: &mut Ui) {
         let mut changed = false;
         let cfg = &mut self.config;
@@ -83,45 +449,113 @@ impl PuzzleView {
             ui.separator();
         }
 
+        let max_shared = cfg.max_shared();
+        cfg.shared = cfg.shared.clamp(1, max_shared);
+        ui.horizontal(|ui| {
+            ui.strong("Shared pieces");
+            ui.add_enabled_ui(max_shared > 1, |ui| {
+                changed |= ui
+                    .add(Slider::new(&mut cfg.shared, 1..=max_shared))
+                    .on_disabled_hover_text(
+                        "Only disks with the same number of sectors can share 2 pieces",
+                    )
+                    .changed();
+            });
+        });
+        // Each disk's slots lie on a circle around its center, and two
+        // circles meet in at most two points.
+        ui.weak("At most 2, and only if both disks have the same number of sectors");
+        ui.separator();
+
+        // Only affects the solved check, so there's no need to reset.
+        ui.checkbox(&mut cfg.supercube, "Supercube").on_hover_text(
+            "Require the sectors of each moving axis to be restored \
+             in addition to the pieces",
+        );
+        ui.separator();
+
         if changed {
             self.reset();
+            self.tracked_piece = None;
         }
     }
 
     pub fn show_puzzle(&mut self, ui: &mut Ui, prefs: &Preferences) {
-        let desired_size = self.config.size();
-
-        let available = ui.available_size_before_wrap();
-
-        let scale = (available / desired_size).min_elem();
-
-        let rect = Rect::from_center_size(
-            ui.available_rect_before_wrap().center(),
-            desired_size * scale,
-        );
+        let available_rect = ui.available_rect_before_wrap();
+        ui.set_clip_rect(ui.clip_rect().intersect(available_rect));
 
         // Generate puzzle if necessary.
         let cfg = self.config;
         self.state.get_or_insert_with(|| PuzzleState::new(cfg));
 
+        let r = ui.interact(available_rect, Id::new("puzzle"), Sense::click_and_drag());
+
+        // Handle two-finger gestures.
+        let now_secs = ui.input(|input| input.time);
+        let multi_touch = ui.input(|input| input.multi_touch());
+        match multi_touch {
+            Some(touch)
+                if self.touch_gesture.is_some() || available_rect.contains(touch.start_pos) =>
+            {
+                let t = self.screen_transform(available_rect, prefs);
+                self.update_touch_gesture(touch, t, prefs, available_rect, now_secs);
+            }
+            _ => {
+                if let Some(TouchGesture::Twist(_)) = self.touch_gesture.take() {
+                    self.finish_drag_twist(false, now_secs, prefs);
+                }
+            }
+        }
+        // The remaining finger of a two-finger gesture shouldn't start a drag.
+        let suppress_drag = multi_touch.is_some() || self.touch_gesture.is_some();
+
+        // Handle zooming and panning.
+        let zoom_delta = ui.input(|input| input.zoom_delta());
+        if multi_touch.is_none() && zoom_delta != 1.0 && r.hovered() {
+            let anchor = r.hover_pos().unwrap_or(available_rect.center());
+            self.zoom_around(anchor - available_rect.center(), zoom_delta);
+        }
+        let is_panning = r.dragged_by(PointerButton::Middle);
+        if is_panning {
+            self.viewport.pan += r.drag_delta();
+        }
+
+        let t = self.screen_transform(available_rect, prefs);
+        if !self
+            .geometry
+            .as_ref()
+            .is_some_and(|g| g.matches(ui.ctx(), cfg, t.scale))
+        {
+            self.geometry = Some(GeometryCache::new(ui.ctx(), cfg, t.scale));
+        }
+        let hit_margin = match prefs.touch_mode {
+            true => TOUCH_HIT_MARGIN,
+            false => 0.0,
+        };
+
         // Compute hovered grip.
-        let r = ui.interact(rect, Id::new("puzzle"), Sense::click_and_drag());
         let hovered_grip = self
             .drag_start
             .or(r.hover_pos())
-            .map(|p| (p - rect.min) / scale)
-            .and_then(|cursor_pos| self.config.hovered_grip(cursor_pos));
+            .map(|p| t.to_puzzle(p))
+            .and_then(|cursor_pos| self.config.hovered_grip_with_margin(cursor_pos, hit_margin));
 
+        // Don't twist while typing, such as when entering a solution.
+        let wants_keyboard_input = ui.ctx().wants_keyboard_input();
         ui.input(|input| {
-            for ev in &input.raw.events {
+            for ev in input.raw.events.iter().filter(|_| !wants_keyboard_input) {
                 if let Event::Key {
                     key,
                     physical_key,
                     pressed: true,
+                    modifiers,
                     ..
                 } = ev
                 {
                     match physical_key.unwrap_or(*key) {
+                        Key::Escape if modifiers.shift => self.animation.cancel_pending(),
+                        Key::Escape => self.animation.finish_all(),
+                        Key::Z if modifiers.command => self.undo(),
                         Key::D => self.twist(Grip::A, TwistDir::Ccw, 1),
                         Key::F => self.twist(Grip::A, TwistDir::Cw, 1),
                         Key::J => self.twist(Grip::B, TwistDir::Ccw, 1),
@@ -133,29 +567,42 @@ impl PuzzleView {
         });
 
         // Handle drag twists.
-        if r.drag_started() {
+        if r.drag_started() && !is_panning && !suppress_drag {
             self.drag_start = r.hover_pos();
+            self.drag_start_time = now_secs;
+            self.drag_velocity.clear();
         }
-        if r.drag_stopped() || r.dragged() && r.hover_pos().is_none() {
-            if let Some((grip, angle)) = self.drag_angle_offset {
-                let amt = (angle / (TAU / cfg.n(grip) as f32)).round() as i32;
-                if amt < 0 {
-                    self.twist_with_initial_angle(grip, TwistDir::Ccw, -angle, (-amt) as u32);
-                } else {
-                    self.twist_with_initial_angle(grip, TwistDir::Cw, -angle, amt as u32);
-                }
-            }
+        if (r.drag_stopped() || r.dragged() && r.hover_pos().is_none())
+            && self.touch_gesture.is_none()
+        {
+            let is_swipe = now_secs - self.drag_start_time < SWIPE_MAX_DURATION
+                && self.drag_start.is_some_and(|start| {
+                    hovered_grip.is_some_and(|grip| {
+                        let d = (t.to_puzzle(start) - cfg.center(grip)).length();
+                        d > cfg.radius(grip) * SWIPE_MIN_RADIUS
+                    })
+                });
+            self.finish_drag_twist(is_swipe, now_secs, prefs);
         }
-        if r.dragged() && r.hover_pos().is_some() {
+        if self.touch_gesture.is_some() {
+            // The gesture controls `drag_angle_offset`.
+            self.drag_start = None;
+        } else if r.dragged()
+            && !is_panning
+            && !suppress_drag
+            && !self.twists_locked
+            && r.hover_pos().is_some()
+        {
             self.animation = TwistAnimationState::default(); // cancel animations
             if let Some(grip) = hovered_grip {
                 if let Some(drag_start) = self.drag_start {
                     let drag_end = r.hover_pos().unwrap_or(drag_start);
-                    let center = rect.min + cfg.center(grip) * scale;
+                    let center = t.to_screen(cfg.center(grip));
                     let init_angle = (drag_start - center).angle();
                     let final_angle = (drag_end - center).angle();
                     let angle_delta = final_angle - init_angle;
                     self.drag_angle_offset = Some((grip, angle_delta));
+                    self.drag_velocity.push(now_secs, angle_delta);
                 }
             }
         } else {
@@ -163,15 +610,20 @@ impl PuzzleView {
             self.drag_angle_offset = None;
         }
 
+        // Handle piece tracking.
+        let tracking_click = r.clicked() && ui.input(|input| input.modifiers.command);
+        if tracking_click {
+            if let Some(click_pos) = r.hover_pos() {
+                self.toggle_tracked_piece(t.to_puzzle(click_pos));
+            }
+        }
+        let clicked = r.clicked() && !tracking_click;
+
         // Handle click & scroll twists.
         if let Some(grip) = hovered_grip {
-            if prefs.sector_click_mode && (r.clicked() || r.secondary_clicked()) {
+            if prefs.sector_click_mode && (clicked || r.secondary_clicked()) {
                 if let Some(click_pos) = r.hover_pos() {
-                    let mut angle = (click_pos - (rect.min + cfg.center(grip) * scale)).angle();
-                    if grip == Grip::B {
-                        angle += PI;
-                    }
-                    let mut sector = (angle / (TAU / cfg.n(grip) as f32)).round() as i32;
+                    let mut sector = self.hovered_sector(grip, click_pos, t);
                     if r.secondary_clicked() {
                         sector = -sector;
                     }
@@ -183,7 +635,7 @@ impl PuzzleView {
                 }
             }
 
-            let amt = (!prefs.sector_click_mode && r.clicked()) as i32
+            let amt = (!prefs.sector_click_mode && clicked) as i32
                 + -((!prefs.sector_click_mode && r.secondary_clicked()) as i32)
                 + ui.input(|input| {
                     input
@@ -194,8 +646,8 @@ impl PuzzleView {
                             Event::MouseWheel {
                                 unit: MouseWheelUnit::Line | MouseWheelUnit::Page,
                                 delta,
-                                modifiers: _,
-                            } => Some((delta.x + delta.y).signum() as i32),
+                                modifiers,
+                            } if !modifiers.command => Some((delta.x + delta.y).signum() as i32),
                             _ => None,
                         })
                         .sum::<i32>()
@@ -209,21 +661,28 @@ impl PuzzleView {
         }
 
         // Update animation state.
-        let now = Instant::now();
-        let delta = match self.last_frame_time {
-            Some(then) => now - then,
-            None => Duration::from_secs_f32(1.0 / ASSUMED_FPS),
-        };
-        if self.animation.proceed(delta, prefs) {
+        if !self.animation_frozen {
+            let now = Instant::now();
+            let delta = match self.last_frame_time {
+                Some(then) => now - then,
+                None => Duration::from_secs_f32(1.0 / ASSUMED_FPS),
+            };
+            let animate = prefs.animate_solves || !self.is_solving();
+            if self.animation.proceed(delta, self.config, prefs, animate) {
+                ui.ctx().request_repaint();
+                self.last_frame_time = Some(now);
+            } else {
+                self.last_frame_time = None;
+            }
+        }
+
+        if self.distance_tables.compute(DISTANCE_TABLE_BUDGET) {
             ui.ctx().request_repaint();
-            self.last_frame_time = Some(now);
-        } else {
-            self.last_frame_time = None;
         }
 
         let moving_grip = Option::or(
             self.drag_angle_offset.map(|(g, _)| g),
-            self.animation.current().map(|(anim, _)| anim.grip),
+            self.animation.current().map(|(anim, _)| anim.twist.grip),
         );
 
         // Draw the moving circle on top of non-moving circle.
@@ -233,10 +692,10 @@ impl PuzzleView {
         };
         let mut is_second = false;
         for g in grip_draw_order {
-            self.draw_grip(ui, g, is_second, rect, scale, prefs);
+            self.draw_grip(ui, g, is_second, t, prefs);
             // Draw non-hovered grips if something is moving.
             if moving_grip.is_some() && hovered_grip != Some(g) {
-                self.draw_grip_circle(ui, g, false, rect, scale);
+                self.draw_grip_circle(ui, g, false, t);
             }
             is_second = true;
         }
@@ -244,13 +703,292 @@ impl PuzzleView {
         // Draw non-hovered grips if neither circle is moving.
         for g in [Grip::A, Grip::B] {
             if hovered_grip != Some(g) && moving_grip.is_none() {
-                self.draw_grip_circle(ui, g, false, rect, scale);
+                self.draw_grip_circle(ui, g, false, t);
             }
         }
 
         // Draw hovered grip.
         if let Some(g) = hovered_grip {
-            self.draw_grip_circle(ui, g, true, rect, scale);
+            self.draw_grip_circle(ui, g, true, t);
+        }
+
+        if prefs.show_tracking_trail && !self.blindfolded {
+            self.draw_tracked_trail(ui, t);
+        }
+
+        // Preview the twist that a left click would do.
+        if prefs.show_twist_preview && moving_grip.is_none() && !self.twists_locked {
+            if let Some((grip, pos)) = hovered_grip.zip(r.hover_pos()) {
+                let amt = match prefs.sector_click_mode {
+                    true => self.hovered_sector(grip, pos, t),
+                    false => 1,
+                };
+                self.draw_twist_preview(ui, grip, amt, t);
+            }
+        }
+
+        if !self.viewport.is_fit() {
+            let button_size = match prefs.touch_mode {
+                true => vec2(60.0, 32.0),
+                false => vec2(40.0, 20.0),
+            };
+            let button_rect = Rect::from_min_size(
+                available_rect.right_top() + vec2(-button_size.x, 0.0),
+                button_size,
+            );
+            if ui.put(button_rect, Button::new("Fit")).clicked() {
+                self.viewport.fit();
+            }
+        }
+    }
+
+    /// Zooms the view by `factor`, keeping the point at `anchor` (relative to
+    /// the center of the available space) in place.
+    fn zoom_around(&mut self, anchor: Vec2, factor: f32) {
+        let new_zoom = (self.viewport.zoom * factor).clamp(MIN_ZOOM, MAX_ZOOM);
+        let factor = new_zoom / self.viewport.zoom;
+        self.viewport.pan = anchor - (anchor - self.viewport.pan) * factor;
+        self.viewport.zoom = new_zoom;
+    }
+
+    /// Updates the current two-finger gesture. Rotating the fingers over a
+    /// disk twists it; pinching or moving them zooms and pans the view.
+    fn update_touch_gesture(
+        &mut self,
+        touch: MultiTouchInfo,
+        t: ScreenTransform,
+        prefs: &Preferences,
+        rect: Rect,
+        now: f64,
+    ) {
+        let gesture = self.touch_gesture.get_or_insert(TouchGesture::Undecided {
+            rotation: 0.0,
+            zoom: 1.0,
+            translation: Vec2::ZERO,
+        });
+        match gesture {
+            TouchGesture::Undecided {
+                rotation,
+                zoom,
+                translation,
+            } => {
+                *rotation += touch.rotation_delta;
+                *zoom *= touch.zoom_delta;
+                *translation += touch.translation_delta;
+                let hit_margin = match prefs.touch_mode {
+                    true => TOUCH_HIT_MARGIN,
+                    false => 0.0,
+                };
+                let grip = self
+                    .config
+                    .hovered_grip_with_margin(t.to_puzzle(touch.start_pos), hit_margin);
+                if rotation.abs() > GESTURE_ROTATION_THRESHOLD {
+                    if let Some(grip) = grip.filter(|_| !self.twists_locked) {
+                        self.drag_angle_offset = Some((grip, *rotation));
+                        self.drag_velocity.clear();
+                        self.drag_velocity.push(now, *rotation);
+                        self.animation = TwistAnimationState::default(); // cancel animations
+                        *gesture = TouchGesture::Twist(grip);
+                    }
+                } else if zoom.ln().abs() > GESTURE_ZOOM_THRESHOLD.ln()
+                    || translation.length() > GESTURE_TRANSLATION_THRESHOLD
+                {
+                    let (zoom, translation) = (*zoom, *translation);
+                    *gesture = TouchGesture::View;
+                    self.zoom_around(touch.center_pos - rect.center(), zoom);
+                    self.viewport.pan += translation;
+                }
+            }
+            TouchGesture::Twist(grip) => {
+                let angle = self.drag_angle_offset.map_or(0.0, |(_, a)| a) + touch.rotation_delta;
+                self.drag_angle_offset = Some((*grip, angle));
+                self.drag_velocity.push(now, angle);
+            }
+            TouchGesture::View => {
+                self.zoom_around(touch.center_pos - rect.center(), touch.zoom_delta);
+                self.viewport.pan += touch.translation_delta;
+            }
+        }
+    }
+
+    /// Twists the disk being dragged to the nearest sector, or with inertia
+    /// enabled, to the sector where it would stop after coasting. If
+    /// `is_swipe` is true, twists by at least one sector in the direction of
+    /// the drag.
+    fn finish_drag_twist(&mut self, is_swipe: bool, now: f64, prefs: &Preferences) {
+        let Some((grip, angle)) = self.drag_angle_offset.take() else {
+            return;
+        };
+        let mut stop_angle = angle;
+        if prefs.drag_inertia {
+            // Add the release angle again so that the sample at release time
+            // is included even if the pointer stayed still before release.
+            self.drag_velocity.push(now, angle);
+            let coast = self.drag_velocity.velocity(now) * INERTIA_COAST_TIME;
+            stop_angle += coast.clamp(-PI, PI);
+        }
+        self.drag_velocity.clear();
+        let sector_angle = TAU / self.config.n(grip) as f32;
+        let mut amt = (stop_angle / sector_angle).round() as i32;
+        if is_swipe && amt == 0 && angle.abs() > SWIPE_MIN_ANGLE {
+            amt = angle.signum() as i32;
+        }
+        if amt < 0 {
+            self.twist_with_initial_angle(grip, TwistDir::Ccw, -angle, (-amt) as u32);
+        } else {
+            self.twist_with_initial_angle(grip, TwistDir::Cw, -angle, amt as u32);
+        }
+    }
+
+    /// Returns the mapping from puzzle coordinates to screen coordinates that
+    /// fits the puzzle in `rect`, with the current viewport applied.
+    fn screen_transform(&self, rect: Rect, prefs: &Preferences) -> ScreenTransform {
+        let mut rotation = self.viewport.rotation;
+        if prefs.rotate_in_portrait && rect.aspect_ratio() < 1.0 {
+            rotation += PI / 2.0;
+        }
+
+        // Fit the bounding box of the rotated puzzle.
+        let size = self.config.size();
+        let (sin, cos) = rotation.sin_cos();
+        let (sin, cos) = (sin.abs(), cos.abs());
+        let bounds = vec2(size.x * cos + size.y * sin, size.x * sin + size.y * cos);
+        let scale = (rect.size() / bounds).min_elem() * self.viewport.zoom;
+
+        let center = rect.center() + self.viewport.pan;
+        ScreenTransform {
+            origin: center - emath::Rot2::from_angle(rotation) * (size * 0.5 * scale),
+            scale,
+            rotation,
+        }
+    }
+
+    pub fn show_view_controls(&mut self, ui: &mut Ui) {
+        let viewport = &mut self.viewport;
+        ui.horizontal(|ui| {
+            ui.add(
+                DragValue::new(&mut viewport.zoom)
+                    .range(MIN_ZOOM..=MAX_ZOOM)
+                    .speed(0.01)
+                    .custom_formatter(|zoom, _| format!("{:.0}%", zoom * 100.0))
+                    .custom_parser(|s| {
+                        s.trim_end_matches('%')
+                            .trim()
+                            .parse::<f64>()
+                            .ok()
+                            .map(|x| x / 100.0)
+                    }),
+            );
+            ui.label("Zoom");
+        });
+        ui.horizontal(|ui| {
+            if ui
+                .button("⟲")
+                .on_hover_text("Rotate 90° counterclockwise")
+                .clicked()
+            {
+                viewport.rotation -= PI / 2.0;
+            }
+            if ui
+                .button("⟳")
+                .on_hover_text("Rotate 90° clockwise")
+                .clicked()
+            {
+                viewport.rotation += PI / 2.0;
+            }
+            viewport.rotation = viewport.rotation.rem_euclid(TAU);
+            ui.drag_angle(&mut viewport.rotation);
+            ui.label("Rotation");
+        });
+        ui.add_enabled_ui(!viewport.is_fit(), |ui| {
+            if ui.button("Fit").clicked() {
+                viewport.fit();
+            }
+        });
+    }
+
+    /// Returns the number of sectors counterclockwise from the intersection to
+    /// the sector of `grip` at `pos`.
+    fn hovered_sector(&self, grip: Grip, pos: Pos2, t: ScreenTransform) -> i32 {
+        let cfg = self.config;
+        let mut angle = (pos - t.to_screen(cfg.center(grip))).angle() - t.rotation;
+        if grip == Grip::B {
+            angle += PI;
+        }
+        angle -= cfg.slot_angle(grip, 0);
+        (angle / (TAU / cfg.n(grip) as f32)).round() as i32
+    }
+
+    /// Draws arrows showing where each piece on `grip` would travel if it were
+    /// twisted `amt` steps counterclockwise.
+    fn draw_twist_preview(&self, ui: &mut Ui, grip: Grip, amt: i32, t: ScreenTransform) {
+        let cfg = self.config;
+        let Some(state) = &self.state else { return };
+        if amt == 0 {
+            return;
+        }
+
+        let mut preview = state.clone();
+        match amt > 0 {
+            true => preview.twist_ccw(cfg, grip, amt as u32),
+            false => preview.twist_cw(cfg, grip, (-amt) as u32),
+        }
+
+        let center = cfg.center(grip);
+        let angle = -amt as f32 * TAU / cfg.n(grip) as f32;
+        let transform = |p: Vec2| t.to_screen(p);
+        let scale = t.scale;
+
+        let color = ui.visuals().selection.stroke.color;
+        let stroke = Stroke {
+            width: 0.015 * scale,
+            color,
+        };
+        let tip_length = 0.08 * scale;
+
+        for (i, piece) in state.pieces(grip).iter().enumerate() {
+            let Some(j) = preview.pieces(grip).iter().position(|p| p == piece) else {
+                continue;
+            };
+            let start = cfg.slot_center(grip, i as u32);
+            let end = transform(cfg.slot_center(grip, j as u32));
+
+            // Shorten the arc a bit so that adjacent arrows don't touch.
+            let resolution = 4 * amt.unsigned_abs();
+            let points: Vec<Pos2> = (0..=resolution)
+                .map(|k| {
+                    let t = crate::util::lerp(0.15, 0.85, k as f32 / resolution as f32);
+                    transform(rotate_point(start, center, angle * t))
+                })
+                .collect();
+            if let [.., prev, last] = points[..] {
+                let dir = (last - prev).normalized() * tip_length;
+                for rot in [PI * 0.85, -PI * 0.85] {
+                    ui.painter()
+                        .line_segment([last, last + emath::Rot2::from_angle(rot) * dir], stroke);
+                }
+            }
+            ui.painter().add(Shape::line(points, stroke));
+            ui.painter().circle_stroke(end, 0.04 * scale, stroke);
+        }
+    }
+
+    fn draw_tracked_trail(&self, ui: &mut Ui, t: ScreenTransform) {
+        let cfg = self.config;
+        let points: Vec<Pos2> = self
+            .tracked_trail
+            .iter()
+            .map(|&(grip, i)| t.to_screen(cfg.slot_center(grip, i)))
+            .collect();
+        let scale = t.scale;
+        let color = ui.visuals().selection.stroke.color;
+        let stroke = Stroke {
+            width: 0.01 * scale,
+            color: color.gamma_multiply(0.75),
+        };
+        ui.painter().add(Shape::line(points.clone(), stroke));
+        for p in points {
+            ui.painter().circle_filled(p, 0.025 * scale, color);
         }
     }
 
@@ -259,36 +997,40 @@ impl PuzzleView {
         ui: &mut Ui,
         grip: Grip,
         draw_intersection: bool,
-        rect: Rect,
-        scale: f32,
+        t: ScreenTransform,
         prefs: &Preferences,
     ) {
         let cfg = self.config;
         let Some(state) = &self.state else { return };
+        let Some(geometry) = &self.geometry else {
+            return;
+        };
 
         let center = cfg.center(grip);
         let radius = cfg.radius(grip);
 
-        let transform = |p: Vec2, angle: f32| rect.min + rotate_point(p, center, angle) * scale;
+        let transform = |p: Vec2, angle: f32| t.to_screen(rotate_point(p, center, angle));
+        let scale = t.scale;
+        let screen_center = t.to_screen(center);
 
         // Define strokes.
-        let sector_stroke = Stroke::NONE;
         let sticker_stroke = Stroke {
             width: 0.005 * scale,
             color: ui.visuals().strong_text_color(),
         };
-
-        // Compute geometry.
-        let init_sphene = cfg.sphene_points();
-        let init_sector: Vec<_> = cfg.sector_points(grip).collect();
+        let tracked_sticker_color = ui.visuals().selection.stroke.color;
 
         // Compute angle offset
         let mut grip_offset = 0.0;
         if let Some((anim, t)) = self.animation.current() {
-            if anim.grip == grip {
+            if anim.twist.grip == grip {
                 // Negate because positive angles are clockwise in egui
-                grip_offset =
-                    -crate::util::animate_twist_angle(anim.initial_angle, anim.final_angle, t);
+                grip_offset = -crate::util::animate_twist_angle(
+                    anim.initial_angle,
+                    anim.final_angle,
+                    t,
+                    prefs.twist_easing,
+                );
             }
         }
         if let Some((g, offset)) = self.drag_angle_offset {
@@ -296,23 +1038,34 @@ impl PuzzleView {
                 grip_offset = offset;
             }
         }
-        let get_angle = |i: u32| i as f32 * TAU / cfg.n(grip) as f32;
+        let get_angle = |i: u32| cfg.slot_angle(grip, i);
+        let get_angle_delta = |i: i32| i as f32 * TAU / cfg.n(grip) as f32;
+
+        let visual_state = self.animation.displayed_state().unwrap_or(state);
 
-        let visual_state = match self.animation.current() {
-            Some((anim, _)) => &anim.state,
-            None => state,
+        let labels = &prefs.labels;
+        let mut font_id = TextStyle::Body.resolve(ui.style());
+        font_id.size *= labels.size;
+        let label_color = ui.visuals().strong_text_color();
+        let label_background = ui
+            .visuals()
+            .panel_fill
+            .gamma_multiply(labels.background_opacity);
+        let show_label = |ui: &Ui, text: String, pos, angle| {
+            let painter = ui.painter();
+            let galley = painter.layout_no_wrap(format!(" {text} "), font_id.clone(), label_color);
+            let rect = Rect::from_center_size(transform(pos, angle), galley.size());
+            painter.rect_filled(rect, 0.0, label_background);
+            painter.galley(rect.min, galley, label_color);
         };
 
-        let show_label = |ui: &mut Ui, text, pos, angle| {
-            ui.put(
-                Rect::from_center_size(transform(pos, angle), vec2(50.0, 50.0)),
-                Label::new(
-                    WidgetText::from(format!(" {text} "))
-                        .color(ui.visuals().strong_text_color())
-                        .background_color(ui.visuals().panel_fill.gamma_multiply(0.8)),
-                )
-                .selectable(false),
-            );
+        let show_glyph = |ui: &Ui, i, pos, angle, background| {
+            ui.painter().extend(super::glyph::glyph_shapes(
+                i,
+                transform(pos, angle),
+                0.05 * scale,
+                crate::colors::contrasting_color(background),
+            ));
         };
 
         if cfg.axis_stationary(grip) {
@@ -323,8 +1076,10 @@ impl PuzzleView {
             );
         }
 
-        // Draw sectors.
-        let make_sector = |angle| init_sector.iter().map(|&p| transform(p, angle)).collect();
+        // Draw sectors. All of them go in one mesh, added before any of the
+        // labels so that the labels are drawn on top.
+        let sectors_idx = ui.painter().add(Shape::Noop);
+        let mut sectors = geometry.sector_mesh(grip);
         for i in 0..cfg.n(grip) {
             let mut j = i;
             let mut angle = get_angle(i);
@@ -333,43 +1088,130 @@ impl PuzzleView {
                 angle += grip_offset;
             }
             let color_index = cfg.color_index_in_grip(grip, j);
-            ui.painter().add(Shape::convex_polygon(
-                make_sector(angle),
-                cfg.sector_color(color_index, ui.visuals().dark_mode),
-                sector_stroke,
-            ));
-            if prefs.show_labels {
-                let pos = crate::util::lerp(
-                    center,
-                    cfg.midpoint(),
-                    (1.0 - (4.0 / cfg.n(grip) as f32)).at_least(1.0 / 3.0),
-                );
-                show_label(ui, cfg.sector_name(grip, j), pos, angle);
+            let color = prefs
+                .colors
+                .sector_color(cfg, color_index, ui.visuals().dark_mode);
+            geometry.add_sector(&mut sectors, grip, screen_center, angle + t.rotation, color);
+            if labels.show_sector_labels {
+                let pos = cfg.sector_label_pos(grip);
+                show_label(ui, labels.sector_name(cfg, grip, j), pos, angle);
+            }
+            if prefs.show_glyphs {
+                let pos = match labels.show_sector_labels {
+                    true => crate::util::lerp(center, cfg.sector_label_pos(grip), 0.55),
+                    false => cfg.sector_label_pos(grip),
+                };
+                show_glyph(ui, color_index, pos, angle, color);
             }
         }
+        ui.painter().set(sectors_idx, sectors);
 
-        // Draw sphenes
-        let make_sphene = |angle| init_sphene.iter().map(|&p| transform(p, angle)).collect();
+        let marker_radius = 0.05 * scale;
+
+        // Draw sphenes, in one mesh like the sectors.
+        let sphenes_idx = ui.painter().add(Shape::Noop);
+        let mut sphenes = geometry.sphene_mesh(grip);
         for i in 0..cfg.n(grip) {
-            if i > 0 || draw_intersection {
+            if cfg.shared_slot(i).is_none() || draw_intersection {
                 let angle = grip_offset + get_angle(i);
                 let sticker = visual_state.pieces(grip)[i as usize];
-                ui.painter().add(Shape::convex_polygon(
-                    make_sphene(angle),
-                    cfg.sticker_color(sticker, ui.visuals().dark_mode),
-                    sticker_stroke,
-                ));
-                if prefs.show_labels {
-                    show_label(ui, cfg.sticker_name(sticker), cfg.midpoint(), angle);
+                let color = match self.blindfolded {
+                    true => ui.visuals().weak_text_color(),
+                    false => prefs
+                        .colors
+                        .sticker_color(cfg, sticker, ui.visuals().dark_mode),
+                };
+                geometry.add_sphene(
+                    &mut sphenes,
+                    grip,
+                    screen_center,
+                    angle + t.rotation,
+                    color,
+                    sticker_stroke.color,
+                );
+                if self.blindfolded {
+                    continue;
+                }
+                if self.tracked_piece == Some(sticker) {
+                    ui.painter().add(geometry.tracked_outline(
+                        grip,
+                        screen_center,
+                        angle + t.rotation,
+                        tracked_sticker_color,
+                    ));
+                }
+                if prefs.show_glyphs {
+                    let pos = match labels.show_sticker_labels {
+                        true => cfg.sphene_center(grip) - vec2(0.0, 0.28),
+                        false => cfg.sphene_center(grip),
+                    };
+                    show_glyph(ui, sticker, pos, angle, color);
+                }
+                if labels.show_sticker_labels {
+                    show_label(
+                        ui,
+                        labels.sticker_name(cfg, sticker),
+                        cfg.sphene_center(grip),
+                        angle,
+                    );
+                }
+                if prefs.show_progress && prefs.show_placement_overlay {
+                    let is_home = cfg.color_index_in_grip(grip, i) == sticker;
+                    let pos =
+                        center + (cfg.sphene_center(grip) - center).normalized() * (radius - 0.08);
+                    ui.painter().circle(
+                        transform(pos, angle),
+                        marker_radius,
+                        if is_home {
+                            Color32::GREEN
+                        } else {
+                            Color32::RED
+                        },
+                        sticker_stroke,
+                    );
                 }
             }
         }
+        ui.painter().set(sphenes_idx, sphenes);
+
+        // Draw an arc from the home position of the sectors to their current
+        // position.
+        if cfg.supercube && !cfg.axis_stationary(grip) {
+            let offset = visual_state.rot_offset(grip);
+            let home_angle = get_angle(0);
+            let current_angle = home_angle - get_angle_delta(offset) + grip_offset;
+            if (current_angle - home_angle).abs() > 0.001 {
+                let sign = match grip {
+                    Grip::A => 1.0,
+                    Grip::B => -1.0,
+                };
+                let rim = center + vec2(radius * 0.9 * sign, 0.0);
+                let resolution = 8 * offset.unsigned_abs().max(1);
+                let points = (0..=resolution)
+                    .map(|i| {
+                        let t = i as f32 / resolution as f32;
+                        transform(rim, crate::util::lerp(home_angle, current_angle, t))
+                    })
+                    .collect();
+                let stroke = Stroke {
+                    width: 0.02 * scale,
+                    color: ui.visuals().strong_text_color(),
+                };
+                ui.painter().add(Shape::line(points, stroke));
+                ui.painter().circle_filled(
+                    transform(rim, home_angle),
+                    0.03 * scale,
+                    ui.visuals().strong_text_color(),
+                );
+            }
+        }
     }
 
-    fn draw_grip_circle(&self, ui: &mut Ui, grip: Grip, is_hovered: bool, rect: Rect, scale: f32) {
+    fn draw_grip_circle(&self, ui: &mut Ui, grip: Grip, is_hovered: bool, t: ScreenTransform) {
         let cfg = self.config;
         let radius = cfg.radius(grip);
         let center = cfg.center(grip);
+        let scale = t.scale;
 
         let stroke = if is_hovered {
             Stroke {
@@ -384,12 +1226,57 @@ impl PuzzleView {
         };
 
         ui.painter().circle_stroke(
-            rect.min + center * scale,
+            t.to_screen(center),
             radius * scale - stroke.width / 2.0,
             stroke,
         );
     }
 
+    /// Applies a twist to the puzzle and queues its animation.
+    pub fn apply_twist(&mut self, twist: Twist) {
+        // Reduce the amount so that the animation doesn't spin many times.
+        let amt = twist.amt % self.config.n(twist.grip);
+        self.twist(twist.grip, twist.dir, amt);
+    }
+
+    /// Sets the progress of the current twist animation, from 0.0 to 1.0.
+    pub(crate) fn set_twist_progress(&mut self, progress: f32) {
+        self.animation.set_progress(progress);
+    }
+
+    /// Stops twist animations from advancing on their own when the puzzle is
+    /// drawn, so that they stay at the progress set by
+    /// [`Self::set_twist_progress()`].
+    pub(crate) fn freeze_animation(&mut self) {
+        self.animation_frozen = true;
+    }
+
+    /// Undoes the last twist in the history, if there is one.
+    pub fn undo(&mut self) {
+        if self.twists_locked {
+            return;
+        }
+        if let Some(twist) = self.history.pop() {
+            let inverse = twist.inverse();
+            self.twist(inverse.grip, inverse.dir, inverse.amt);
+            self.history.pop();
+        }
+    }
+
+    /// Instantly completes all queued twist animations.
+    pub fn skip_animations(&mut self) {
+        self.animation.finish_all();
+    }
+    /// Instantly completes all queued twist animations except the current
+    /// one.
+    pub fn skip_pending_animations(&mut self) {
+        self.animation.cancel_pending();
+    }
+
+    pub fn state(&self) -> Option<&PuzzleState> {
+        self.state.as_ref()
+    }
+
     fn twist(&mut self, grip: Grip, direction: TwistDir, amt: u32) {
         self.twist_with_initial_angle(grip, direction, 0.0, amt);
     }
@@ -401,12 +1288,18 @@ impl PuzzleView {
         initial_angle: f32,
         amt: u32,
     ) {
+        if self.twists_locked {
+            return;
+        }
+        if self.tracked_trail.is_empty() {
+            self.record_tracked_position();
+        }
         if let Some(state) = &mut self.state {
-            let old_state = state.clone();
-            match direction {
-                TwistDir::Cw => state.twist_cw(grip, amt),
-                TwistDir::Ccw => state.twist_ccw(grip, amt),
-            }
+            let twist = Twist {
+                grip,
+                dir: direction,
+                amt,
+            };
             let mut final_angle = match grip {
                 Grip::A => TAU / self.config.a as f32 * direction.to_f32() * amt as f32,
                 Grip::B => TAU / self.config.b as f32 * direction.to_f32() * amt as f32,
@@ -417,18 +1310,158 @@ impl PuzzleView {
             if initial_angle - PI > final_angle {
                 final_angle += TAU;
             }
-            self.animation.push(TwistAnimation {
-                state: old_state,
-                grip,
-                initial_angle,
-                final_angle,
-            });
+            self.animation.push(
+                state,
+                TwistAnimation {
+                    twist,
+                    initial_angle,
+                    final_angle,
+                },
+            );
+            twist.apply(self.config, state);
+            if amt > 0 {
+                self.history.push(twist);
+            }
         }
+        self.record_tracked_position();
     }
 }
 
-fn rotate_point(p: Vec2, center: Vec2, angle: f32) -> Vec2 {
-    let Vec2 { x, y } = p - center;
-    let (sin, cos) = angle.sin_cos();
-    center + vec2(cos * x - sin * y, sin * x + cos * y)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCREEN: Rect = Rect::from_min_max(Pos2::ZERO, pos2(800.0, 600.0));
+    /// Distance of each finger from the center of the gesture.
+    const FINGER_RADIUS: f32 = 40.0;
+
+    /// Headless app that feeds touch events to a puzzle view.
+    struct Harness {
+        ctx: Context,
+        view: PuzzleView,
+        prefs: Preferences,
+        time: f64,
+    }
+
+    impl Harness {
+        fn new() -> Self {
+            let config = PuzzleConfig::default();
+            Self {
+                ctx: Context::default(),
+                view: PuzzleView::new(config, PuzzleState::new(config)),
+                prefs: Preferences {
+                    drag_inertia: false,
+                    ..Default::default()
+                },
+                time: 0.0,
+            }
+        }
+
+        /// Returns the screen position of the center of a disk.
+        fn disk_center(&self, grip: Grip) -> Pos2 {
+            let t = self.view.screen_transform(SCREEN, &self.prefs);
+            t.to_screen(self.view.config.center(grip))
+        }
+
+        /// Runs one frame with `events`.
+        fn frame(&mut self, events: Vec<Event>) {
+            self.time += 1.0 / 60.0;
+            let input = RawInput {
+                screen_rect: Some(SCREEN),
+                time: Some(self.time),
+                events,
+                ..Default::default()
+            };
+            let _ = self.ctx.run(input, |ctx| {
+                CentralPanel::default()
+                    .frame(Frame::NONE)
+                    .show(ctx, |ui| self.view.show_puzzle(ui, &self.prefs));
+            });
+        }
+
+        /// Runs a two-finger gesture around `center`, with the fingers moving
+        /// from `radius` apart and `angle` to `end_radius` and `end_angle`
+        /// over several frames, then lifted.
+        fn two_finger_gesture(
+            &mut self,
+            center: Pos2,
+            (radius, angle): (f32, f32),
+            (end_radius, end_angle): (f32, f32),
+        ) {
+            const STEPS: usize = 10;
+            // Backends also move the pointer with the first finger, which
+            // egui needs to start a gesture.
+            let fingers = |phase, t: f32| {
+                let radius = egui::lerp(radius..=end_radius, t);
+                let angle = egui::lerp(angle..=end_angle, t);
+                let pos = |id: u64| center + Vec2::angled(angle + id as f32 * PI) * radius;
+                let touch = |id| Event::Touch {
+                    device_id: TouchDeviceId(0),
+                    id: TouchId(id),
+                    phase,
+                    pos: pos(id),
+                    force: None,
+                };
+                vec![Event::PointerMoved(pos(0)), touch(0), touch(1)]
+            };
+            // egui starts the gesture a frame after the fingers touch down.
+            self.frame(fingers(TouchPhase::Start, 0.0));
+            self.frame(fingers(TouchPhase::Move, 0.0));
+            for i in 1..=STEPS {
+                self.frame(fingers(TouchPhase::Move, i as f32 / STEPS as f32));
+            }
+            self.frame(fingers(TouchPhase::End, 1.0));
+            self.frame(vec![]);
+        }
+    }
+
+    #[test]
+    fn test_rotate_gesture_twists_disk() {
+        let mut h = Harness::new();
+        let center = h.disk_center(Grip::A);
+        let sector = TAU / h.view.config.n(Grip::A) as f32;
+
+        h.two_finger_gesture(center, (FINGER_RADIUS, 0.0), (FINGER_RADIUS, sector));
+        assert_eq!(h.view.history().len(), 1);
+        let twist = h.view.history()[0];
+        assert_eq!((twist.grip, twist.amt), (Grip::A, 1));
+
+        // Rotating back undoes the twist.
+        h.two_finger_gesture(center, (FINGER_RADIUS, sector), (FINGER_RADIUS, 0.0));
+        assert_eq!(h.view.history(), [twist, twist.inverse()]);
+        assert!(h.view.touch_gesture.is_none());
+        assert!(h.view.drag_angle_offset.is_none());
+    }
+
+    #[test]
+    fn test_small_rotation_does_not_twist() {
+        let mut h = Harness::new();
+        let center = h.disk_center(Grip::A);
+        h.two_finger_gesture(center, (FINGER_RADIUS, 0.0), (FINGER_RADIUS, 0.2));
+        assert!(h.view.history().is_empty());
+    }
+
+    #[test]
+    fn test_pinch_gesture_zooms() {
+        let mut h = Harness::new();
+        let center = h.disk_center(Grip::A);
+        h.two_finger_gesture(center, (FINGER_RADIUS, 0.0), (FINGER_RADIUS * 2.0, 0.0));
+        assert!(h.view.history().is_empty());
+        assert!(
+            (h.view.viewport.zoom - 2.0).abs() < 0.01,
+            "zoom is {}",
+            h.view.viewport.zoom,
+        );
+    }
+
+    #[test]
+    fn test_gesture_locked_twists() {
+        let mut h = Harness::new();
+        h.view.set_twists_locked(true);
+        let center = h.disk_center(Grip::A);
+        let sector = TAU / h.view.config.n(Grip::A) as f32;
+        h.two_finger_gesture(center, (FINGER_RADIUS, 0.0), (FINGER_RADIUS, sector));
+        assert!(h.view.history().is_empty());
+        assert!(h.view.drag_angle_offset.is_none());
+    }
 }
@@ -0,0 +1,276 @@
+//! Competition rounds: several timed solves of scrambles generated from a
+//! published seed, so that every competitor gets the same scrambles.
+
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use web_time::Duration;
+
+use crate::timer::{Penalty, SolveResult};
+use crate::{Algorithm, PuzzleConfig, PuzzleState};
+
+/// One solve in a round.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoundSolve {
+    pub result: SolveResult,
+    /// Twists applied during the solve, in notation.
+    pub moves: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Round {
+    pub config: PuzzleConfig,
+    /// Seed from which the scrambles are generated.
+    pub seed: u64,
+    /// Number of solves in the round.
+    pub count: usize,
+    pub solves: Vec<RoundSolve>,
+}
+
+/// Contents of a results file.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+struct RoundFile {
+    round: Round,
+    /// SHA-1 hash of the round, to detect accidental corruption of the file.
+    /// Anyone can recompute it, so it doesn't prove that the times are
+    /// genuine; only the moves can be checked, by replaying them.
+    checksum: String,
+}
+
+impl Round {
+    pub fn new(config: PuzzleConfig, seed: u64, count: usize) -> Self {
+        Self {
+            config,
+            seed,
+            count,
+            solves: vec![],
+        }
+    }
+
+    /// Returns the scramble for solve `i`. The first scramble is the same as
+    /// the one drawn by the command-line `--scramble` option with the same
+    /// seed.
+    ///
+    /// The generator is named explicitly rather than using `StdRng`, whose
+    /// output may change between versions of `rand`, so that seeds published
+    /// for a round stay valid.
+    pub fn scramble(&self, i: usize) -> PuzzleState {
+        let mut rng = ChaCha8Rng::seed_from_u64(self.seed);
+        let mut state = PuzzleState::random(self.config, &mut rng);
+        for _ in 0..i {
+            state = PuzzleState::random(self.config, &mut rng);
+        }
+        state
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.solves.len() >= self.count
+    }
+
+    /// Returns the average of the round, or `None` if it isn't complete.
+    ///
+    /// Rounds of three solves use the mean. Longer rounds drop the best and
+    /// worst 5% of solves (at least one each), counting DNFs as the worst,
+    /// and average the rest. The average is a DNF if any of the remaining
+    /// solves is.
+    pub fn average(&self) -> Option<SolveResult> {
+        if !self.is_complete() || self.solves.is_empty() {
+            return None;
+        }
+        let mut times: Vec<Option<Duration>> = self
+            .solves
+            .iter()
+            .map(|solve| solve.result.final_time())
+            .collect();
+        times.sort_by_key(|time| (time.is_none(), *time));
+        let trim = match times.len() {
+            0..=3 => 0,
+            n => n.div_ceil(20),
+        };
+        let counting = &times[trim..times.len() - trim];
+        let total = counting.iter().copied().sum::<Option<Duration>>();
+        Some(match total {
+            Some(total) => SolveResult {
+                time: total / counting.len() as u32,
+                penalty: Penalty::None,
+            },
+            None => SolveResult {
+                time: Duration::ZERO,
+                penalty: Penalty::Dnf,
+            },
+        })
+    }
+
+    /// Returns a results file for the round.
+    pub fn to_file(&self) -> Result<String, String> {
+        let file = RoundFile {
+            round: self.clone(),
+            checksum: self.checksum()?,
+        };
+        ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Reads a results file and verifies its checksum and every solve that
+    /// isn't a DNF by replaying its moves on its scramble.
+    pub fn from_file(s: &str) -> Result<Self, String> {
+        let file: RoundFile = ron::from_str(s).map_err(|e| e.to_string())?;
+        let round = file.round;
+        if file.checksum != round.checksum()? {
+            return Err("checksum does not match; the file is corrupted".to_string());
+        }
+        round.config.validate()?;
+        if round.solves.len() > round.count {
+            return Err(format!(
+                "round has {} solves but should have {}",
+                round.solves.len(),
+                round.count,
+            ));
+        }
+        for (i, solve) in round.solves.iter().enumerate() {
+            let alg: Algorithm = solve
+                .moves
+                .parse()
+                .map_err(|e| format!("solve {}: {e}", i + 1))?;
+            let mut state = round.scramble(i);
+            alg.apply(round.config, &mut state);
+            if solve.result.penalty != Penalty::Dnf && !state.is_solved(round.config) {
+                return Err(format!(
+                    "solve {} does not solve its scramble but is not a DNF",
+                    i + 1,
+                ));
+            }
+        }
+        Ok(round)
+    }
+
+    fn checksum(&self) -> Result<String, String> {
+        let data = ron::to_string(self).map_err(|e| e.to_string())?;
+        let hash = Sha1::digest(data.as_bytes());
+        Ok(hash.iter().map(|byte| format!("{byte:02x}")).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::place_pieces;
+
+    fn result(millis: u64, penalty: Penalty) -> SolveResult {
+        SolveResult {
+            time: Duration::from_millis(millis),
+            penalty,
+        }
+    }
+
+    fn round_with_results(results: &[SolveResult]) -> Round {
+        let mut round = Round::new(PuzzleConfig::default(), 0, results.len());
+        round.solves = results
+            .iter()
+            .map(|&result| RoundSolve {
+                result,
+                moves: String::new(),
+            })
+            .collect();
+        round
+    }
+
+    /// Returns a round of `F4F2`, where both axes are fixed, with a genuine
+    /// solution for its first scramble and a DNF.
+    fn solved_round() -> Round {
+        let config = "F4F2".parse().expect("invalid puzzle");
+        let mut round = Round::new(config, 42, 3);
+        let pieces: Vec<u32> = (0..config.piece_count()).collect();
+        let solution = place_pieces(config, &round.scramble(0), &pieces).expect("no solution");
+        round.solves = vec![
+            RoundSolve {
+                result: result(12_340, Penalty::None),
+                moves: solution.to_string(),
+            },
+            RoundSolve {
+                result: result(0, Penalty::Dnf),
+                moves: "L".to_string(),
+            },
+        ];
+        round
+    }
+
+    #[test]
+    fn test_average() {
+        use Penalty::*;
+        let average = |results: &[SolveResult]| round_with_results(results).average();
+
+        let thirds = |millis: u64| SolveResult {
+            time: Duration::from_millis(millis) / 3,
+            penalty: None,
+        };
+
+        let mean = average(&[
+            result(1000, None),
+            result(2000, PlusTwo),
+            result(6000, None),
+        ]);
+        assert_eq!(mean, Some(thirds(11_000)));
+
+        let ao5 = [1000, 3000, 2000, 9000, 4000].map(|ms| result(ms, None));
+        assert_eq!(average(&ao5), Some(result(3000, None)));
+
+        let mut one_dnf = ao5;
+        one_dnf[0] = result(0, Dnf);
+        assert_eq!(average(&one_dnf), Some(thirds(16_000)));
+
+        let mut two_dnfs = one_dnf;
+        two_dnfs[1] = result(0, Dnf);
+        assert_eq!(average(&two_dnfs).map(|r| r.penalty), Some(Dnf));
+
+        let mut incomplete = round_with_results(&ao5);
+        incomplete.count = 6;
+        assert_eq!(incomplete.average(), Option::None);
+        assert_eq!(average(&[]), Option::None);
+    }
+
+    #[test]
+    fn test_file_round_trip() {
+        let round = solved_round();
+        let file = round.to_file().expect("failed to save round");
+        assert_eq!(Round::from_file(&file), Ok(round));
+    }
+
+    #[test]
+    fn test_file_edited() {
+        let file = solved_round().to_file().expect("failed to save round");
+        let edited = file.replacen("secs: 12", "secs: 11", 1);
+        assert_ne!(edited, file);
+        assert!(Round::from_file(&edited).is_err());
+        assert!(Round::from_file("").is_err());
+    }
+
+    #[test]
+    fn test_file_invalid_round() {
+        let check = |round: Round| {
+            let file = round.to_file().expect("failed to save round");
+            Round::from_file(&file)
+        };
+
+        let mut round = solved_round();
+        round.config.a = 0;
+        assert!(check(round).is_err());
+
+        let mut round = solved_round();
+        round.config.shared = 9;
+        assert!(check(round).is_err());
+
+        let mut round = solved_round();
+        round.count = 1;
+        assert!(check(round).is_err());
+
+        let mut round = solved_round();
+        round.solves[0].moves = "L X".to_string();
+        assert!(check(round).is_err());
+
+        let mut round = solved_round();
+        round.solves[0].moves.push_str(" L");
+        assert!(check(round).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! Speedsolving timer with competition-style inspection and penalties.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use web_time::{Duration, Instant};
+
+use crate::util::format_duration;
+
+/// Inspection time allowed before the solve must start.
+pub const INSPECTION_TIME: Duration = Duration::from_secs(15);
+/// Inspection time after which starting the solve is a DNF instead of +2.
+const INSPECTION_DNF_TIME: Duration = Duration::from_secs(17);
+/// Inspection times at which the solver is warned.
+pub const INSPECTION_WARNINGS: [Duration; 2] = [Duration::from_secs(8), Duration::from_secs(12)];
+/// How long Space must be held before releasing it starts the timer.
+const HOLD_TIME: Duration = Duration::from_millis(500);
+
+/// How the timer is started and stopped.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum TimerStart {
+    /// The first twist starts the timer and solving the puzzle stops it.
+    #[default]
+    FirstTwist,
+    /// Holding and releasing Space starts the timer and pressing Space stops
+    /// it, like a Stackmat timer.
+    Stackmat,
+}
+
+impl fmt::Display for TimerStart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimerStart::FirstTwist => write!(f, "First twist"),
+            TimerStart::Stackmat => write!(f, "Hold Space"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Penalty {
+    #[default]
+    None,
+    /// Two seconds added to the time.
+    PlusTwo,
+    /// Did not finish.
+    Dnf,
+}
+
+/// Time and penalty of a finished solve.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq)]
+pub struct SolveResult {
+    /// Time on the timer, without the penalty.
+    pub time: Duration,
+    pub penalty: Penalty,
+}
+impl SolveResult {
+    /// Returns the time including the penalty, or `None` for a DNF.
+    pub fn final_time(&self) -> Option<Duration> {
+        match self.penalty {
+            Penalty::None => Some(self.time),
+            Penalty::PlusTwo => Some(self.time + Duration::from_secs(2)),
+            Penalty::Dnf => None,
+        }
+    }
+}
+
+impl fmt::Display for SolveResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.final_time() {
+            Some(time) if self.penalty == Penalty::PlusTwo => {
+                write!(f, "{}+", format_duration(time))
+            }
+            Some(time) => write!(f, "{}", format_duration(time)),
+            None => write!(f, "DNF"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum SolveTimer {
+    /// The puzzle hasn't been scrambled.
+    #[default]
+    Idle,
+    /// The puzzle has been scrambled and the solve hasn't started.
+    Ready {
+        /// Start of inspection, if inspection is enabled.
+        inspection_start: Option<Instant>,
+        /// Time when Space was pressed, if it is being held.
+        hold_start: Option<Instant>,
+    },
+    Running {
+        start: Instant,
+        /// Penalty for inspecting too long.
+        penalty: Penalty,
+    },
+    Stopped(SolveResult),
+}
+
+impl SolveTimer {
+    /// Returns a timer for a newly scrambled puzzle.
+    pub fn scrambled(inspection: bool) -> Self {
+        Self::Ready {
+            inspection_start: inspection.then(Instant::now),
+            hold_start: None,
+        }
+    }
+
+    /// Returns whether the timer should be redrawn every frame.
+    pub fn is_running(&self) -> bool {
+        matches!(self, Self::Ready { .. } | Self::Running { .. })
+    }
+
+    /// Starts the solve, if it hasn't started yet.
+    pub fn start(&mut self) {
+        if let Self::Ready {
+            inspection_start, ..
+        } = *self
+        {
+            *self = Self::Running {
+                start: Instant::now(),
+                penalty: inspection_start
+                    .map_or(Penalty::None, |start| inspection_penalty(start.elapsed())),
+            };
+        }
+    }
+
+    /// Stops the solve, given whether the puzzle is solved.
+    pub fn stop(&mut self, solved: bool) {
+        if let Self::Running { start, penalty } = *self {
+            *self = Self::Stopped(SolveResult {
+                time: start.elapsed(),
+                penalty: if solved { penalty } else { Penalty::Dnf },
+            });
+        }
+    }
+
+    /// Handles Space being pressed in Stackmat mode.
+    pub fn press_space(&mut self, solved: bool) {
+        match self {
+            Self::Ready { hold_start, .. } => {
+                hold_start.get_or_insert_with(Instant::now);
+            }
+            Self::Running { .. } => self.stop(solved),
+            _ => (),
+        }
+    }
+    /// Handles Space being released in Stackmat mode.
+    pub fn release_space(&mut self) {
+        if let Self::Ready { hold_start, .. } = self {
+            if hold_start.is_some_and(|t| t.elapsed() >= HOLD_TIME) {
+                self.start();
+            } else {
+                *hold_start = None;
+            }
+        }
+    }
+
+    /// Records a DNF if inspection has gone on too long to start the solve.
+    pub fn update(&mut self) {
+        if let Self::Ready {
+            inspection_start: Some(start),
+            hold_start: None,
+        } = *self
+        {
+            if start.elapsed() > INSPECTION_DNF_TIME {
+                *self = Self::Stopped(SolveResult {
+                    time: Duration::ZERO,
+                    penalty: Penalty::Dnf,
+                });
+            }
+        }
+    }
+
+    /// Returns the time on the timer.
+    pub fn elapsed(&self) -> Duration {
+        match self {
+            Self::Idle | Self::Ready { .. } => Duration::ZERO,
+            Self::Running { start, .. } => start.elapsed(),
+            Self::Stopped(result) => result.time,
+        }
+    }
+
+    /// Returns the time spent inspecting, if inspection is in progress.
+    pub fn inspection_elapsed(&self) -> Option<Duration> {
+        match self {
+            Self::Ready {
+                inspection_start: Some(start),
+                ..
+            } => Some(start.elapsed()),
+            _ => None,
+        }
+    }
+
+    /// Returns whether Space has been held long enough to start the timer on
+    /// release.
+    pub fn is_held_long_enough(&self) -> Option<bool> {
+        match self {
+            Self::Ready {
+                hold_start: Some(t),
+                ..
+            } => Some(t.elapsed() >= HOLD_TIME),
+            _ => None,
+        }
+    }
+}
+
+/// Returns the penalty for starting a solve after inspecting for `elapsed`.
+fn inspection_penalty(elapsed: Duration) -> Penalty {
+    if elapsed <= INSPECTION_TIME {
+        Penalty::None
+    } else if elapsed <= INSPECTION_DNF_TIME {
+        Penalty::PlusTwo
+    } else {
+        Penalty::Dnf
+    }
+}
@@ -0,0 +1,232 @@
+//! Guided trainer: partial goals on the way to solving the puzzle, each
+//! practiced from a position where the goals before it are already done.
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use web_time::{Duration, Instant};
+
+use crate::{Grip, LabelScheme, PuzzleConfig, PuzzleState, place_pieces};
+
+/// Number of scrambles to try before accepting one that already meets the
+/// goal.
+const SCRAMBLE_ATTEMPTS: usize = 10;
+
+/// Partial goal on the way to solving the puzzle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Goal {
+    pub name: String,
+    /// Name with classic labels, which identifies the goal in saved times
+    /// whatever labels are shown.
+    id: String,
+    /// Pieces that must be in their home slots, including those of earlier
+    /// goals, or `None` if the whole puzzle must be solved.
+    pub pieces: Option<Vec<u32>>,
+}
+
+impl Goal {
+    /// Returns the goals for a puzzle, in order, with pieces named using
+    /// `labels`. Each goal includes the ones before it.
+    pub fn all(config: PuzzleConfig, labels: &LabelScheme) -> Vec<Self> {
+        let a = config.n(Grip::A);
+        let shared = config.shared;
+        let goal = |name: String, pieces| Self {
+            id: name.clone(),
+            name,
+            pieces,
+        };
+        let mut goals = vec![goal(
+            match shared {
+                1 => "Restore the shared piece and one neighbour".to_string(),
+                _ => "Restore the shared pieces and one neighbour".to_string(),
+            },
+            Some((0..=shared).collect()),
+        )];
+        let last = u32::max(3, shared + 1);
+        if last < a - 1 {
+            let range = |first: String, last: String| format!("Place pieces {first}–{last}");
+            goals.push(Self {
+                name: range(
+                    labels.sticker_name(config, 1),
+                    labels.sticker_name(config, last),
+                ),
+                id: range(config.sticker_name(1), config.sticker_name(last)),
+                pieces: Some((0..=last).collect()),
+            });
+        }
+        if shared + 1 < a {
+            goals.push(goal(
+                "Solve the left disk's pieces".to_string(),
+                Some((0..a).collect()),
+            ));
+        }
+        goals.push(goal("Solve the puzzle".to_string(), None));
+        goals
+    }
+
+    /// Returns the key under which times for the goal are saved.
+    pub fn stats_key(&self, config: PuzzleConfig) -> String {
+        format!("{config}: {}", self.id)
+    }
+
+    pub fn is_done(&self, config: PuzzleConfig, state: &PuzzleState) -> bool {
+        match &self.pieces {
+            Some(pieces) => pieces.iter().all(|&p| state.is_piece_home(config, p)),
+            None => state.is_solved(config),
+        }
+    }
+}
+
+/// Statistics of the times in which a goal has been done.
+#[derive(Serialize, Deserialize, Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct GoalTimes {
+    pub count: u32,
+    pub best: Duration,
+    pub total: Duration,
+}
+
+impl GoalTimes {
+    pub fn add(&mut self, time: Duration) {
+        if self.count == 0 || time < self.best {
+            self.best = time;
+        }
+        self.count += 1;
+        self.total += time;
+    }
+
+    /// Returns the mean time, or `None` if the goal hasn't been done.
+    pub fn mean(&self) -> Option<Duration> {
+        self.total.checked_div(self.count)
+    }
+}
+
+/// Returns a random position in which every goal before `goal` is done but
+/// `goal` itself usually isn't, or `None` if the earlier goals have too many
+/// pieces to set up.
+pub fn starting_position(
+    config: PuzzleConfig,
+    goal: usize,
+    rng: &mut impl Rng,
+) -> Option<PuzzleState> {
+    let goals = Goal::all(config, &LabelScheme::default());
+    let previous = goal.checked_sub(1).and_then(|i| goals[i].pieces.as_ref());
+    let mut ret = None;
+    for _ in 0..SCRAMBLE_ATTEMPTS {
+        let mut state = PuzzleState::random(config, rng);
+        if let Some(pieces) = previous {
+            place_pieces(config, &state, pieces)?.apply(config, &mut state);
+        }
+        let done = goals[goal].is_done(config, &state);
+        ret = Some(state);
+        if !done {
+            break;
+        }
+    }
+    ret
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum TrainerPhase {
+    /// Waiting for the first twist.
+    Ready,
+    Running {
+        start: Instant,
+    },
+    /// The goal is done, in the given time.
+    Done(Duration),
+}
+
+/// Attempt at one goal.
+#[derive(Debug, Clone)]
+pub struct TrainerAttempt {
+    pub config: PuzzleConfig,
+    pub goals: Vec<Goal>,
+    /// Index of the current goal.
+    pub goal: usize,
+    pub phase: TrainerPhase,
+}
+
+impl TrainerAttempt {
+    pub fn new(config: PuzzleConfig, goal: usize, labels: &LabelScheme) -> Self {
+        Self {
+            config,
+            goals: Goal::all(config, labels),
+            goal,
+            phase: TrainerPhase::Ready,
+        }
+    }
+
+    pub fn current_goal(&self) -> &Goal {
+        &self.goals[self.goal]
+    }
+    pub fn has_next_goal(&self) -> bool {
+        self.goal + 1 < self.goals.len()
+    }
+
+    /// Starts timing at the first twist and stops once the goal is done.
+    /// Returns the time when the goal is first done.
+    pub fn update(&mut self, state: &PuzzleState, twisted: bool) -> Option<Duration> {
+        if self.phase == TrainerPhase::Ready && twisted {
+            self.phase = TrainerPhase::Running {
+                start: Instant::now(),
+            };
+        }
+        if let TrainerPhase::Running { start } = self.phase {
+            if self.current_goal().is_done(self.config, state) {
+                let time = start.elapsed();
+                self.phase = TrainerPhase::Done(time);
+                return Some(time);
+            }
+        }
+        None
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self.phase {
+            TrainerPhase::Ready => Duration::ZERO,
+            TrainerPhase::Running { start } => start.elapsed(),
+            TrainerPhase::Done(time) => time,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LabelStyle;
+
+    #[test]
+    fn test_goal_names() {
+        let config = PuzzleConfig::default();
+        let numbers = LabelScheme {
+            style: LabelStyle::Numbers,
+            ..Default::default()
+        };
+        let classic = Goal::all(config, &LabelScheme::default());
+        let numbered = Goal::all(config, &numbers);
+        assert_eq!(classic[1].name, "Place pieces A–C");
+        assert_eq!(numbered[1].name, "Place pieces 1–3");
+
+        // Saved times don't depend on the labels.
+        assert_eq!(classic.len(), numbered.len());
+        for (a, b) in classic.iter().zip(&numbered) {
+            assert_eq!(a.stats_key(config), b.stats_key(config));
+            assert_eq!(a.pieces, b.pieces);
+        }
+        assert_eq!(numbered[1].stats_key(config), "M5F2: Place pieces A–C");
+    }
+
+    #[test]
+    fn test_goal_times() {
+        let mut times = GoalTimes::default();
+        assert_eq!(times.mean(), None);
+        for secs in [3, 1, 5] {
+            times.add(Duration::from_secs(secs));
+        }
+        assert_eq!(times.count, 3);
+        assert_eq!(times.best, Duration::from_secs(1));
+        assert_eq!(times.mean(), Some(Duration::from_secs(3)));
+
+        let saved = ron::to_string(&times).expect("failed to save times");
+        assert_eq!(ron::from_str(&saved), Ok(times));
+    }
+}
@@ -1,11 +1,16 @@
-use std::{
-    f32::consts::PI,
-    ops::{Add, Mul},
-};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::ops::{Add, Mul};
 
-/// Interpolates between `a` and `b` in a smooth curve.
-pub fn animate_twist_angle(a: f32, b: f32, t: f32) -> f32 {
-    lerp(a, b, (1.0 - (t * PI).cos()) / 2.0)
+use egui::{Vec2, vec2};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use web_time::{Duration, Instant};
+
+use crate::Easing;
+
+/// Interpolates between `a` and `b` following `easing`.
+pub fn animate_twist_angle(a: f32, b: f32, t: f32, easing: Easing) -> f32 {
+    lerp(a, b, easing.apply(t))
 }
 
 /// Linearly interpolates (unclamped) between two numbers.
@@ -15,3 +20,38 @@ where
 {
     a * (1.0 - t) + b * t
 }
+
+/// Returns a random number generator seeded from the current time, different
+/// each time.
+pub fn time_seeded_rng() -> StdRng {
+    // this is awful seeding but it's fine for this puzzle and I couldn't
+    // get `getrandom` to work on web
+    let mut h = DefaultHasher::new();
+    Instant::now().hash(&mut h);
+    let bytes = h.finish().to_ne_bytes();
+    StdRng::from_seed(
+        [bytes; 4]
+            .as_flattened()
+            .try_into()
+            .expect("error casting [[u8; 8]; 4] to [u8; 32]"),
+    )
+}
+
+/// Rotates `p` around `center` by `angle`, which is clockwise in screen space.
+pub fn rotate_point(p: Vec2, center: Vec2, angle: f32) -> Vec2 {
+    let Vec2 { x, y } = p - center;
+    let (sin, cos) = angle.sin_cos();
+    center + vec2(cos * x - sin * y, sin * x + cos * y)
+}
+
+/// Formats a duration as seconds with hundredths, with minutes if at least one
+/// minute: `12.34` or `1:02.34`.
+pub fn format_duration(duration: Duration) -> String {
+    let centis = duration.as_millis() / 10;
+    let (minutes, centis) = (centis / 6000, centis % 6000);
+    let (secs, centis) = (centis / 100, centis % 100);
+    match minutes {
+        0 => format!("{secs}.{centis:02}"),
+        _ => format!("{minutes}:{secs:02}.{centis:02}"),
+    }
+}